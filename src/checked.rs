@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+/// Integers whose arithmetic reports overflow and division by zero as `None` instead of wrapping or panicking
+pub(crate) trait CheckedInt: Copy + PartialEq + Display {
+    fn zero() -> Self;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! checked_int {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedInt for $t {
+                fn zero() -> Self { 0 }
+                fn checked_add(self, rhs: Self) -> Option<Self> { <$t>::checked_add(self, rhs) }
+                fn checked_sub(self, rhs: Self) -> Option<Self> { <$t>::checked_sub(self, rhs) }
+                fn checked_mul(self, rhs: Self) -> Option<Self> { <$t>::checked_mul(self, rhs) }
+                fn checked_div(self, rhs: Self) -> Option<Self> { <$t>::checked_div(self, rhs) }
+                fn checked_rem(self, rhs: Self) -> Option<Self> { <$t>::checked_rem(self, rhs) }
+            }
+        )*
+    };
+}
+
+checked_int!(i32, i64, i128, u64, u128, usize);
+
+pub(crate) fn gcd<T: CheckedInt>(a: T, b: T) -> T {
+    if b == T::zero() { a } else { gcd(b, a.checked_rem(b).unwrap()) }
+}
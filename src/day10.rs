@@ -1,66 +1,283 @@
-enum Operation {
-    Noop,
-    Addx(i32)
-}
-
-impl Operation {
-    fn parse(input: &str) -> Operation {
-        let words: Vec<_> = input.split(" ").collect();
-            
-        match words[0] {
-            "addx" => Operation::Addx(words[1].parse::<i32>().unwrap()),
-            _ => Operation::Noop
+use std::{env, fmt};
+use std::io::{self, BufRead, Write};
+
+/// What one kind of instruction costs and does, `effect` is applied when its last cycle ends
+pub(crate) struct InstructionSpec {
+    pub mnemonic: &'static str,
+    pub cycles: usize,
+    pub operands: usize,
+    pub effect: fn(&mut Cpu, &[i32]),
+}
+
+/// Everything the handheld understands, new instructions only need a row here
+pub(crate) const INSTRUCTION_SET: [InstructionSpec; 2] = [
+    InstructionSpec { mnemonic: "noop", cycles: 1, operands: 0, effect: |_, _| {} },
+    InstructionSpec { mnemonic: "addx", cycles: 2, operands: 1, effect: |cpu, args| cpu.reg_x += args[0] },
+];
+
+#[derive(Clone)]
+pub(crate) struct Instruction {
+    pub spec: &'static InstructionSpec,
+    pub args: Vec<i32>,
+}
+
+impl Instruction {
+    pub fn parse(input: &str) -> Result<Instruction, String> {
+        let words: Vec<_> = input.split_whitespace().collect();
+        let mnemonic = *words.first().ok_or("Empty instruction")?;
+        let spec = INSTRUCTION_SET
+            .iter()
+            .find(|s| s.mnemonic == mnemonic)
+            .ok_or(format!("Unknown opcode {}", mnemonic))?;
+
+        if words.len() - 1 != spec.operands {
+            return Err(format!("{} takes {} operands, got {}", mnemonic, spec.operands, words.len() - 1));
         }
+        let args = words[1..]
+            .iter()
+            .map(|w| w.parse::<i32>().map_err(|e| format!("Invalid operand {} for {}: {}", w, mnemonic, e)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Instruction { spec, args })
     }
 }
 
-struct Cpu {
-    reg_x: i32,
-    history_x: Vec<i32>
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spec.mnemonic)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of the CPU during one cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Cycle {
+    /// Starts at 1 like in the puzzle
+    pub number: usize,
+    pub reg_x: i32,
+}
+
+/// Gets told about every cycle while the CPU runs
+pub(crate) trait CycleObserver {
+    fn on_cycle(&mut self, cycle: &Cycle);
+}
+
+impl<F: FnMut(&Cycle)> CycleObserver for F {
+    fn on_cycle(&mut self, cycle: &Cycle) {
+        self(cycle)
+    }
+}
+
+pub(crate) struct Cpu {
+    pub reg_x: i32,
+    pub history_x: Vec<i32>,
+    pub program: Vec<Instruction>,
+    /// Index of the instruction being executed
+    pub pc: usize,
+    /// Cycles already spent on the current instruction
+    pub busy: usize,
+    pub cycle: usize,
 }
 
 impl Cpu {
-    fn new() -> Cpu {
+    pub fn new(program: Vec<Instruction>) -> Cpu {
         Cpu {
             reg_x: 1,
-            history_x: Vec::new()
+            history_x: Vec::new(),
+            program,
+            pc: 0,
+            busy: 0,
+            cycle: 0,
+        }
+    }
+
+    pub fn load(input: &str) -> Result<Cpu, String> {
+        let program = input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| Instruction::parse(l).map_err(|e| format!("Line {}: {}", i + 1, e)))
+            .collect::<Result<_, _>>()?;
+        Ok(Cpu::new(program))
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    /// Runs a single cycle and returns the state during it, `None` once the program is over
+    pub fn step(&mut self) -> Option<Cycle> {
+        let instruction = self.program.get(self.pc)?.clone();
+
+        self.cycle += 1;
+        let during = Cycle { number: self.cycle, reg_x: self.reg_x };
+        self.history_x.push(self.reg_x);
+
+        self.busy += 1;
+        if self.busy == instruction.spec.cycles {
+            (instruction.spec.effect)(self, &instruction.args);
+            self.pc += 1;
+            self.busy = 0;
+        }
+        Some(during)
+    }
+
+    pub fn run(&mut self, observers: &mut [&mut dyn CycleObserver]) {
+        while let Some(cycle) = self.step() {
+            for observer in observers.iter_mut() {
+                observer.on_cycle(&cycle);
+            }
+        }
+    }
+}
+
+/// Sums cycle number times X during the 20th cycle and every 40 cycles after that
+#[derive(Default)]
+pub(crate) struct SignalStrength {
+    pub sum: i32,
+}
+
+impl CycleObserver for SignalStrength {
+    fn on_cycle(&mut self, cycle: &Cycle) {
+        if cycle.number % 40 == 20 {
+            self.sum += cycle.number as i32 * cycle.reg_x;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Breakpoint {
+    /// Stops once the given cycle has run
+    Cycle(usize),
+    /// Stops once X changes to the given value
+    X(i32),
+}
+
+const DEBUGGER_HELP: &str = "\
+s [n]          step n cycles, 1 by default
+c              continue until a breakpoint or the end of the program
+n              run until the next addx is about to start
+b cycle <n>    break after cycle n
+b x <v>        break when X becomes v
+clear          remove all breakpoints
+r              print the registers
+h              print history_x
+q              quit";
+
+/// Steps through a program with commands read line by line, see `DEBUGGER_HELP`
+pub(crate) struct Debugger {
+    pub cpu: Cpu,
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Debugger {
+        Debugger { cpu, breakpoints: Vec::new() }
+    }
+
+    fn registers(&self) -> String {
+        let current = self.cpu.program.get(self.cpu.pc).map_or("halted".to_owned(), |i| i.to_string());
+        format!("cycle {} X={} pc={} ({}, {} cycles in)", self.cpu.cycle, self.cpu.reg_x, self.cpu.pc, current, self.cpu.busy)
+    }
+
+    /// Steps once and tells which breakpoint was hit, if any
+    fn step(&mut self) -> Option<Option<Breakpoint>> {
+        let before = self.cpu.reg_x;
+        self.cpu.step()?;
+        let hit = self.breakpoints.iter().copied().find(|b| match b {
+            Breakpoint::Cycle(n) => self.cpu.cycle == *n,
+            Breakpoint::X(v) => self.cpu.reg_x == *v && before != *v,
+        });
+        Some(hit)
+    }
+
+    /// Steps until `stop` holds or a breakpoint is hit, always at least once
+    fn run_until(&mut self, out: &mut impl Write, stop: impl Fn(&Cpu) -> bool) -> io::Result<()> {
+        loop {
+            match self.step() {
+                None => return writeln!(out, "Program finished after {} cycles", self.cpu.cycle),
+                Some(Some(breakpoint)) => return writeln!(out, "Hit {:?}", breakpoint),
+                Some(None) if stop(&self.cpu) => return Ok(()),
+                Some(None) => {}
+            }
         }
     }
-    
-    fn step(&mut self, op: Operation) {
-        match op {
-            Operation::Noop => {
-                self.history_x.push(self.reg_x);
+
+    fn execute(&mut self, command: &[&str], out: &mut impl Write) -> io::Result<()> {
+        match command {
+            ["s"] | ["step"] => self.run_until(out, |_| true)?,
+            ["s", n] | ["step", n] => match n.parse::<usize>() {
+                Ok(n) => {
+                    let target = self.cpu.cycle + n;
+                    self.run_until(out, |cpu| cpu.cycle >= target)?
+                }
+                Err(e) => writeln!(out, "Invalid cycle count {}: {}", n, e)?,
             },
-            Operation::Addx(v) => {
-                self.history_x.push(self.reg_x);
-                self.history_x.push(self.reg_x);
-                self.reg_x += v;
+            ["c"] | ["continue"] => self.run_until(out, |_| false)?,
+            ["n"] | ["next"] => self.run_until(out, |cpu| {
+                cpu.busy == 0 && cpu.program.get(cpu.pc).is_some_and(|i| i.spec.mnemonic == "addx")
+            })?,
+            ["b", kind, value] | ["break", kind, value] => {
+                let breakpoint = match *kind {
+                    "cycle" => value.parse().map(Breakpoint::Cycle).map_err(|e| e.to_string()),
+                    "x" => value.parse().map(Breakpoint::X).map_err(|e| e.to_string()),
+                    _ => Err(format!("Unknown breakpoint kind {}", kind)),
+                };
+                match breakpoint {
+                    Ok(b) => {
+                        self.breakpoints.push(b);
+                        writeln!(out, "Breakpoint {:?}", b)?
+                    }
+                    Err(e) => writeln!(out, "Invalid breakpoint: {}", e)?,
+                }
+            }
+            ["clear"] => self.breakpoints.clear(),
+            ["r"] | ["regs"] => {}
+            ["h"] | ["history"] => writeln!(out, "{:?}", self.cpu.history_x)?,
+            ["help"] => writeln!(out, "{}", DEBUGGER_HELP)?,
+            _ => writeln!(out, "Unknown command {}, try help", command.join(" "))?,
+        }
+        if !matches!(command, ["h"] | ["history"] | ["help"]) {
+            writeln!(out, "{}", self.registers())?;
+        }
+        Ok(())
+    }
+
+    /// Reads commands until `q` or the end of the input
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "{}", self.registers())?;
+        for line in input.lines() {
+            let line = line?;
+            let command: Vec<&str> = line.split_whitespace().collect();
+            match command[..] {
+                [] => continue,
+                ["q"] | ["quit"] => break,
+                _ => self.execute(&command, &mut out)?,
             }
         }
+        Ok(())
     }
-    
 }
 
+fn part1(input: String) -> i32 {
+    let mut cpu = Cpu::load(&input).unwrap();
+    let mut signal = SignalStrength::default();
+    cpu.run(&mut [&mut signal]);
+    signal.sum
+}
+
+/// Pass `debug` as second argument to step through the program instead
 pub fn process(input: String) {
-    let ops: Vec<_> = input.lines().map(Operation::parse).collect();
-    
-    let mut cpu = Cpu::new();
-    for op in ops {
-        cpu.step(op);
-    }
-    
-    let mut i = 20; // index of cycle 20 is 19
-    let mut sum = 0;
-    while i < cpu.history_x.len() {
-        println!("Cycle {} value {}", i, cpu.history_x[i - 1]);
-        
-        sum += cpu.history_x[i - 1] * (i as i32);
-        i += 40;
-    }
-    
-    dbg!(cpu.history_x);
-    println!("Product is {}", sum);
+    if env::args().nth(2).as_deref() == Some("debug") {
+        let mut debugger = Debugger::new(Cpu::load(&input).unwrap());
+        debugger.run(io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
+    let result = part1(input);
+    println!("Sum of signal strengths is {}", result);
 }
 
 #[cfg(test)]
@@ -69,7 +286,73 @@ mod tests_part10 {
     use super::*;
 
     #[test]
-    fn test_get_next_tail_position() {
+    fn test_step() {
+        let mut cpu = Cpu::load("noop\naddx 3\naddx -5").unwrap();
+        let cycles: Vec<_> = std::iter::from_fn(|| cpu.step()).map(|c| c.reg_x).collect();
+        assert_eq!(cycles, vec![1, 1, 1, 4, 4]);
+        assert_eq!(cpu.reg_x, -1);
+        assert_eq!(cpu.history_x, cycles);
+        assert!(cpu.is_halted());
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        assert_eq!(Cpu::load("noop\nmulx 2").err(), Some("Line 2: Unknown opcode mulx".to_owned()));
+        assert!(Cpu::load("addx").is_err());
+        assert!(Cpu::load("addx x").is_err());
+    }
+
+    fn debug(program: &str, commands: &str) -> (Debugger, String) {
+        let mut debugger = Debugger::new(Cpu::load(program).unwrap());
+        let mut out = Vec::new();
+        debugger.run(commands.as_bytes(), &mut out).unwrap();
+        (debugger, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_debugger_steps() {
+        let (debugger, out) = debug("noop\naddx 3\naddx -5", "s\ns 2\nh\nr");
+        assert_eq!(debugger.cpu.cycle, 3);
+        assert_eq!(debugger.cpu.reg_x, 4);
+        assert!(out.contains("[1, 1, 1]"));
+        assert!(out.ends_with("cycle 3 X=4 pc=2 (addx -5, 0 cycles in)\n"));
+    }
+
+    #[test]
+    fn test_debugger_breakpoints() {
+        let program = "noop\naddx 3\nnoop\naddx -3\nnoop\naddx 3\nnoop";
+        let (debugger, out) = debug(program, "b x 4\nb cycle 7\nc\nc\nc\nc\nq\ns");
+        assert!(out.contains("Hit X(4)\ncycle 3 X=4"));
+        assert!(out.contains("Hit Cycle(7)\ncycle 7 X=1"));
+        assert!(out.contains("Hit X(4)\ncycle 9 X=4"));
+        assert!(out.contains("Program finished after 10 cycles"));
+        assert_eq!(debugger.cpu.cycle, 10);
+
+        // The X breakpoint only fires when X changes, not on every cycle where X stays 4
+        let (debugger, _) = debug(program, "b x 4\nc\nc");
+        assert_eq!(debugger.cpu.cycle, 9);
+
+        let (debugger, out) = debug(program, "b cycle 7\nclear\nc");
+        assert_eq!(debugger.cpu.cycle, 10);
+        assert!(!out.contains("Hit"));
+    }
 
+    #[test]
+    fn test_debugger_next_addx() {
+        let (debugger, _) = debug("noop\nnoop\naddx 3\nnoop\naddx 1", "n");
+        assert_eq!((debugger.cpu.cycle, debugger.cpu.pc), (2, 2));
+        let (debugger, _) = debug("noop\nnoop\naddx 3\nnoop\naddx 1", "n\nn");
+        assert_eq!((debugger.cpu.cycle, debugger.cpu.pc, debugger.cpu.reg_x), (5, 4, 4));
+        let (_, out) = debug("noop\nnoop\naddx 3\nnoop\naddx 1", "b cycle 3\nb q 1\nn\nn\nbogus");
+        assert!(out.contains("Invalid breakpoint: Unknown breakpoint kind q"));
+        assert!(out.contains("Hit Cycle(3)"));
+        assert!(out.contains("Unknown command bogus"));
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day10_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 13140);
     }
 }
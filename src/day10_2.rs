@@ -1,77 +1,254 @@
-enum Operation {
-    Noop,
-    Addx(i32)
+use std::{env, fs};
+use std::io;
+use std::path::Path;
+use crate::day10::{Cpu, Cycle, CycleObserver};
+use crate::image::{Color, Image};
+
+pub(crate) const CRT_WIDTH: usize = 40;
+pub(crate) const CRT_HEIGHT: usize = 6;
+const GLYPH_WIDTH: usize = 4;
+/// Glyph columns plus the empty column separating them
+const GLYPH_SPACING: usize = 5;
+
+/// The letters of the 4x6 font the elves' CRT draws, row by row
+const GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// How many image pixels wide and high a CRT pixel is when written to a file
+const SCALE: usize = 8;
+
+const DARK: Color = [15, 15, 35];
+const LIT: Color = [255, 255, 102];
+const SPRITE: Color = [40, 120, 200];
+const BEAM: Color = [230, 40, 40];
+
+/// The beam draws one pixel per cycle, it is lit when the 3 pixel wide sprite centered on X covers it
+pub(crate) struct Crt {
+    pub pixels: Vec<bool>,
 }
 
-impl Operation {
-    fn parse(input: &str) -> Operation {
-        let words: Vec<_> = input.split(" ").collect();
-            
-        match words[0] {
-            "addx" => Operation::Addx(words[1].parse::<i32>().unwrap()),
-            _ => Operation::Noop
+impl Crt {
+    pub fn new() -> Crt {
+        Crt { pixels: vec![false; CRT_WIDTH * CRT_HEIGHT] }
+    }
+
+    pub fn render(&self) -> String {
+        self.pixels
+            .chunks(CRT_WIDTH)
+            .map(|row| row.iter().map(|lit| if *lit { '#' } else { '.' }).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn image(&self) -> Image {
+        Image {
+            width: CRT_WIDTH,
+            height: CRT_HEIGHT,
+            pixels: self.pixels.iter().map(|lit| if *lit { LIT } else { DARK }).collect(),
         }
     }
+
+    /// Reads the letters drawn on the screen
+    pub fn decode(&self) -> Result<String, String> {
+        (0..CRT_WIDTH / GLYPH_SPACING)
+            .map(|letter| {
+                let glyph: String = (0..CRT_HEIGHT)
+                    .flat_map(|y| (0..GLYPH_WIDTH).map(move |x| (x + letter * GLYPH_SPACING, y)))
+                    .map(|(x, y)| if self.pixels[y * CRT_WIDTH + x] { '#' } else { '.' })
+                    .collect();
+                GLYPHS
+                    .iter()
+                    .find(|(_, g)| *g == glyph)
+                    .map(|(c, _)| *c)
+                    .ok_or(format!("Unknown glyph at letter {}", letter + 1))
+            })
+            .collect()
+    }
 }
 
-struct Cpu {
-    reg_x: i32,
-    history_x: Vec<i32>
+impl CycleObserver for Crt {
+    fn on_cycle(&mut self, cycle: &Cycle) {
+        let position = (cycle.number - 1) % self.pixels.len();
+        let column = (position % CRT_WIDTH) as i32;
+        self.pixels[position] = (column - cycle.reg_x).abs() <= 1;
+    }
 }
 
-impl Cpu {
-    fn new() -> Cpu {
-        Cpu {
-            reg_x: 1,
-            history_x: Vec::new()
+/// Draws the screen after every cycle with the sprite on the beam's row and the beam itself highlighted
+pub(crate) struct FrameRecorder {
+    pub crt: Crt,
+    pub frames: Vec<Image>,
+}
+
+impl FrameRecorder {
+    pub fn new() -> FrameRecorder {
+        FrameRecorder { crt: Crt::new(), frames: Vec::new() }
+    }
+
+    /// Writes the frames as numbered PPM files into `dir`
+    pub fn save(&self, dir: &Path, scale: usize) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            frame.scaled(scale).save(&dir.join(format!("frame_{:03}.ppm", i + 1)))?;
         }
+        Ok(())
     }
-    
-    fn step(&mut self, op: Operation) {
-        match op {
-            Operation::Noop => {
-                self.history_x.push(self.reg_x);
-            },
-            Operation::Addx(v) => {
-                self.history_x.push(self.reg_x);
-                self.history_x.push(self.reg_x);
-                self.reg_x += v;
+}
+
+impl CycleObserver for FrameRecorder {
+    fn on_cycle(&mut self, cycle: &Cycle) {
+        self.crt.on_cycle(cycle);
+
+        let mut frame = self.crt.image();
+        let position = (cycle.number - 1) % self.crt.pixels.len();
+        let row = position / CRT_WIDTH;
+        for x in cycle.reg_x - 1..=cycle.reg_x + 1 {
+            if (0..CRT_WIDTH as i32).contains(&x) {
+                frame.pixels[row * CRT_WIDTH + x as usize] = SPRITE;
             }
         }
+        frame.pixels[position] = BEAM;
+        self.frames.push(frame);
     }
-    
 }
 
+fn part2(input: String) -> String {
+    let mut cpu = Cpu::load(&input).unwrap();
+    let mut crt = Crt::new();
+    cpu.run(&mut [&mut crt]);
+    crt.decode().unwrap_or_else(|e| panic!("{}\n{}", e, crt.render()))
+}
+
+/// Optionally writes the screen to the image file given as second argument
+/// and a frame per cycle into the directory given as third argument
 pub fn process(input: String) {
-    let ops: Vec<_> = input.lines().map(Operation::parse).collect();
-    
-    let mut cpu = Cpu::new();
-    for op in ops {
-        cpu.step(op);
-    }
-    
-    for i in 0..240 { // CRT cycles
-        let x = i % 40;
-        if x == 0 {
-            println!()
-        }
-        let sprite_loc = cpu.history_x.get(i).unwrap();
-        
-        if (x as i32 >= sprite_loc - 1) && (x as i32 <= sprite_loc + 1) {
-            print!("#")
-        } else {
-            print!(".");
-        }
+    let mut cpu = Cpu::load(&input).unwrap();
+    let mut recorder = FrameRecorder::new();
+    cpu.run(&mut [&mut recorder]);
+
+    if let Some(path) = env::args().nth(2) {
+        recorder.crt.image().scaled(SCALE).save(Path::new(&path)).expect("Could not write the image");
+        println!("Wrote the screen to {}", path);
+    }
+    if let Some(dir) = env::args().nth(3) {
+        recorder.save(Path::new(&dir), SCALE).expect("Could not write the frames");
+        println!("Wrote {} frames to {}", recorder.frames.len(), dir);
     }
+
+    println!("{}", recorder.crt.render());
+    println!("Letters on the CRT: {}", recorder.crt.decode().unwrap_or_else(|e| e));
 }
 
 #[cfg(test)]
 mod tests_part10 {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use crate::day10::SignalStrength;
+
+    const EXAMPLE_IMAGE: &str = "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+    #[test]
+    fn test_observers_share_one_run() {
+        let mut cpu = Cpu::load(include_str!("../data/day10_dry.txt")).unwrap();
+        let mut crt = Crt::new();
+        let mut signal = SignalStrength::default();
+        let mut cycles = 0;
+        let mut counter = |_: &Cycle| cycles += 1;
+        cpu.run(&mut [&mut crt, &mut signal, &mut counter]);
+
+        assert_eq!(cycles, 240);
+        assert_eq!(signal.sum, 13140);
+        assert_eq!(crt.render(), EXAMPLE_IMAGE);
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut crt = Crt::new();
+        for (i, (_, glyph)) in GLYPHS.iter().take(8).enumerate() {
+            for (p, c) in glyph.chars().enumerate() {
+                crt.pixels[(p / GLYPH_WIDTH) * CRT_WIDTH + i * GLYPH_SPACING + p % GLYPH_WIDTH] = c == '#';
+            }
+        }
+        assert_eq!(crt.decode(), Ok("ABCEFGHI".to_owned()));
+
+        crt.pixels[0] = !crt.pixels[0];
+        assert_eq!(crt.decode(), Err("Unknown glyph at letter 1".to_owned()));
+    }
 
     #[test]
-    fn test_get_next_tail_position() {
+    fn test_example2() {
+        // The example does not draw letters
+        let input = include_str!("../data/day10_dry.txt");
+        let mut cpu = Cpu::load(input).unwrap();
+        let mut crt = Crt::new();
+        cpu.run(&mut [&mut crt]);
+        assert!(crt.decode().is_err());
+    }
+
+    #[test]
+    fn test_ppm() {
+        let mut crt = Crt::new();
+        crt.pixels[1] = true;
+        let image = crt.image().scaled(2);
+        assert_eq!((image.width, image.height), (80, 12));
+
+        let mut out = Vec::new();
+        image.write_ppm(&mut out).unwrap();
+        let header = b"P6\n80 12\n255\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(out.len(), header.len() + 80 * 12 * 3);
+
+        let pixel = |x: usize, y: usize| &out[header.len() + (y * 80 + x) * 3..][..3];
+        assert_eq!(pixel(1, 1), DARK);
+        assert_eq!(pixel(2, 0), LIT);
+        assert_eq!(pixel(3, 1), LIT);
+        assert_eq!(pixel(4, 0), DARK);
+    }
 
+    #[test]
+    fn test_frames() {
+        let mut cpu = Cpu::load(include_str!("../data/day10_dry.txt")).unwrap();
+        let mut recorder = FrameRecorder::new();
+        cpu.run(&mut [&mut recorder]);
+
+        assert_eq!(recorder.frames.len(), 240);
+        assert_eq!(recorder.crt.render(), EXAMPLE_IMAGE);
+
+        // During cycle 1 X is 1, so the sprite covers the first three pixels and the beam the first one
+        let first = &recorder.frames[0].pixels;
+        assert_eq!(&first[..4], &[BEAM, SPRITE, SPRITE, DARK]);
+        // The last frame shows the beam on the bottom right pixel
+        assert_eq!(recorder.frames[239].pixels[239], BEAM);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = include_str!("../data/day10_sharp.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, "FBURHZCH");
     }
 }
@@ -1,130 +1,582 @@
-use std::collections::LinkedList;
-use regex::Regex;
+use std::collections::{HashMap, LinkedList};
+use std::env;
+use std::fmt::Display;
+use std::hash::Hash;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, not_line_ending, one_of, space0, space1, u64};
+use nom::combinator::{all_consuming, map, value};
+use nom::IResult;
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use itertools::Itertools;
+use crate::checked::{gcd, CheckedInt};
 
-#[derive(Debug)]
-enum Operation {
-    Multiply(i32),
-    Add(i32),
-    Square
+/// Integers worry levels can be kept in
+pub(crate) trait Worry: CheckedInt + TryFrom<u64> {}
+
+impl<T: CheckedInt + TryFrom<u64>> Worry for T {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BinOp {
+    Add,
+    Subtract,
+    Multiply,
 }
 
-impl Operation {
-    pub fn parse(input: &str) -> Operation {
-        // * 19
-        let words: Vec<_> = input.split(" ").collect();
-        match (words[0], words[1]) {
-            ("*", "old") => Operation::Square,
-            ("*", v) => Operation::Multiply(v.parse().unwrap()),
-            (_, v) => Operation::Add(v.parse().unwrap())
+impl BinOp {
+    fn symbol(&self) -> char {
+        match self {
+            BinOp::Add => '+',
+            BinOp::Subtract => '-',
+            BinOp::Multiply => '*',
         }
     }
-    
-    pub fn apply(&self, input: i32) -> i32 {
+}
+
+/// The right hand side of `new = ...`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Old,
+    Number(u64),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+
+impl Expr {
+    /// Multiplication binds tighter than addition and subtraction, all of them are left associative
+    pub fn parse(input: &str) -> Result<Expr, String> {
+        all_consuming(terminated(Expr::sum, space0))(input)
+            .map(|(_, expr)| expr)
+            .map_err(|e| format!("Invalid operation {}: {}", input, e))
+    }
+
+    fn sum(input: &str) -> IResult<&str, Expr> {
+        let (input, first) = Expr::product(input)?;
+        let op = preceded(space0, alt((value(BinOp::Add, tag("+")), value(BinOp::Subtract, tag("-")))));
+        let (input, rest) = many0(pair(op, Expr::product))(input)?;
+        Ok((input, Expr::fold(first, rest)))
+    }
+
+    fn product(input: &str) -> IResult<&str, Expr> {
+        let (input, first) = Expr::atom(input)?;
+        let op = preceded(space0, value(BinOp::Multiply, one_of("*")));
+        let (input, rest) = many0(pair(op, Expr::atom))(input)?;
+        Ok((input, Expr::fold(first, rest)))
+    }
+
+    fn atom(input: &str) -> IResult<&str, Expr> {
+        preceded(space0, alt((
+            value(Expr::Old, tag("old")),
+            map(u64, Expr::Number),
+            delimited(tag("("), Expr::sum, preceded(space0, tag(")"))),
+        )))(input)
+    }
+
+    fn fold(first: Expr, rest: Vec<(BinOp, Expr)>) -> Expr {
+        rest.into_iter().fold(first, |left, (op, right)| Expr::Binary(Box::new(left), op, Box::new(right)))
+    }
+
+    pub fn evaluate<T: Worry>(&self, old: T) -> Result<T, String> {
         match self {
-            Operation::Multiply(v) => input * v,
-            Operation::Add(v) => input + v,
-            Operation::Square => input * input
+            Expr::Old => Ok(old),
+            Expr::Number(n) => T::try_from(*n).map_err(|_| format!("{} does not fit the worry level type", n)),
+            Expr::Binary(left, op, right) => {
+                let (left, right) = (left.evaluate(old)?, right.evaluate(old)?);
+                let result = match op {
+                    BinOp::Add => left.checked_add(right),
+                    BinOp::Subtract => left.checked_sub(right),
+                    BinOp::Multiply => left.checked_mul(right),
+                };
+                result.ok_or(format!("{} {} {} overflows", left, op.symbol(), right))
+            }
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Field {
+    Header,
+    StartingItems,
+    Operation,
+    Test,
+    IfTrue,
+    IfFalse,
+}
+
+impl Field {
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Header => "Monkey",
+            Field::StartingItems => "Starting items",
+            Field::Operation => "Operation",
+            Field::Test => "Test",
+            Field::IfTrue => "If true",
+            Field::IfFalse => "If false",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParseMonkeyError {
+    /// The monkey's id, or its position in the notes when the id itself is wrong
+    pub monkey: usize,
+    pub field: Field,
+    pub reason: String,
+}
+
+impl Display for ParseMonkeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Monkey {}, {}: {}", self.monkey, self.field.label(), self.reason)
+    }
+}
+
+impl std::error::Error for ParseMonkeyError {}
+
+/// Reads the next non blank line which has to start with the label of `field`,
+/// indentation, trailing whitespace and `\r` are ignored
+fn field_line<'a, O>(
+    input: &'a str,
+    monkey: usize,
+    field: Field,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<(&'a str, O), ParseMonkeyError> {
+    let input = input.trim_start();
+    let (line, rest) = input.split_at(input.find('\n').unwrap_or(input.len()));
+    let line = line.trim_end();
+    all_consuming(preceded(tag(field.label()), parser))(line)
+        .map(|(_, value)| (rest, value))
+        .map_err(|_| ParseMonkeyError { monkey, field, reason: format!("cannot read `{}`", line) })
+}
+
+fn to_worry<T: Worry>(n: u64, monkey: usize, field: Field) -> Result<T, ParseMonkeyError> {
+    T::try_from(n).map_err(|_| ParseMonkeyError { monkey, field, reason: format!("{} does not fit the worry level type", n) })
+}
+
 #[derive(Debug)]
-struct Monkey {
-    id: u8,
-    items: LinkedList<i32>,
-    operation: Operation,
-    test: i32,
-    next_positive: u8,
-    next_negative: u8,
-    score: u32
+pub(crate) struct Monkey<T> {
+    pub id: usize,
+    pub items: LinkedList<T>,
+    pub operation: Expr,
+    pub test: T,
+    pub next_positive: usize,
+    pub next_negative: usize,
+    pub score: u64,
 }
 
-impl Monkey {
+impl<T: Worry> Monkey<T> {
     // Monkey 0:
-    // Starting items: 79, 98
-    // Operation: new = old * 19
-    // Test: divisible by 23
-    // If true: throw to monkey 2
-    // If false: throw to monkey 3
-    
-    pub fn parse(input: &str) -> Option<Monkey> {
-        let lines: Vec<_> = input.lines().collect();
-        let re_id : Regex = Regex::new(r"Monkey (\d+):").unwrap();
-        let id = re_id.captures(lines[0].trim())?.get(1)?.as_str().parse().unwrap();
-
-        let re_starting_items : Regex = Regex::new(r"Starting items: ([,\s\d]*)").unwrap();
-        let starting_items = re_starting_items
-            .captures(lines[1].trim())?
-            .get(1)
-            .unwrap()
-            .as_str()
-            .split(", ")
-            .map(|i| i.parse::<i32>().unwrap())
-            .collect();
-
-        let re_op : Regex = Regex::new(r"Operation: new = old ([\*\+]\s[0-9a-z]+)").unwrap();
-        let op = re_op.captures(lines[2].trim())?.get(1)?.as_str();
-
-        let re_test : Regex = Regex::new(r"Test: divisible by (\d+)").unwrap();
-        let test = re_test.captures(lines[3].trim())?.get(1)?.as_str().parse().unwrap();
-
-        let re_next_positive : Regex = Regex::new(r"If true: throw to monkey (\d+)").unwrap();
-        let next_positive = re_next_positive.captures(lines[4].trim())?.get(1)?.as_str().parse().unwrap();
-
-        let re_next_negative : Regex = Regex::new(r"If false: throw to monkey (\d+)").unwrap();
-        let next_negative = re_next_negative.captures(lines[5].trim())?.get(1)?.as_str().parse().unwrap();
-                
-        Some(Monkey {
+    //   Starting items: 79, 98
+    //   Operation: new = old * 19
+    //   Test: divisible by 23
+    //     If true: throw to monkey 2
+    //     If false: throw to monkey 3
+    pub fn parse(input: &str, position: usize) -> Result<(&str, Monkey<T>), ParseMonkeyError> {
+        let colon = || pair(char(':'), space0);
+        let (input, id) = field_line(input, position, Field::Header, delimited(space1, u64, char(':')))?;
+        let id = id as usize;
+        if id != position {
+            return Err(ParseMonkeyError { monkey: position, field: Field::Header, reason: format!("expected monkey {} here, got {}", position, id) });
+        }
+
+        let (input, items) = field_line(input, id, Field::StartingItems, preceded(colon(), separated_list0(delimited(space0, char(','), space0), u64)))?;
+        let items = items
+            .into_iter()
+            .map(|i| to_worry(i, id, Field::StartingItems))
+            .collect::<Result<_, _>>()?;
+
+        let (input, operation) = field_line(input, id, Field::Operation, preceded(tuple((colon(), tag("new"), space0, char('='))), not_line_ending))?;
+        let operation = Expr::parse(operation).map_err(|reason| ParseMonkeyError { monkey: id, field: Field::Operation, reason })?;
+
+        let (input, test) = field_line(input, id, Field::Test, preceded(pair(colon(), pair(tag("divisible by"), space1)), u64))?;
+        if test == 0 {
+            return Err(ParseMonkeyError { monkey: id, field: Field::Test, reason: "cannot be divisible by 0".to_owned() });
+        }
+        let test = to_worry(test, id, Field::Test)?;
+
+        let throw = || preceded(pair(colon(), pair(tag("throw to monkey"), space1)), u64);
+        let (input, next_positive) = field_line(input, id, Field::IfTrue, throw())?;
+        let (input, next_negative) = field_line(input, id, Field::IfFalse, throw())?;
+
+        Ok((input, Monkey {
             id,
-            items: starting_items,
-            operation: Operation::parse(op),
+            items,
+            operation,
             test,
-            next_positive,
-            next_negative,
-            score: 0
-        })
+            next_positive: next_positive as usize,
+            next_negative: next_negative as usize,
+            score: 0,
+        }))
+    }
+
+    /// Parses all the monkeys and checks that they only throw to monkeys which exist
+    pub fn parse_all(input: &str) -> Result<Vec<Monkey<T>>, ParseMonkeyError> {
+        let mut monkeys = Vec::new();
+        let mut input = input;
+        while !input.trim().is_empty() {
+            let (rest, monkey) = Monkey::parse(input, monkeys.len())?;
+            monkeys.push(monkey);
+            input = rest;
+        }
+
+        for monkey in &monkeys {
+            for (field, target) in [(Field::IfTrue, monkey.next_positive), (Field::IfFalse, monkey.next_negative)] {
+                if target >= monkeys.len() || target == monkey.id {
+                    return Err(ParseMonkeyError { monkey: monkey.id, field, reason: format!("cannot throw to monkey {}", target) });
+                }
+            }
+        }
+        Ok(monkeys)
     }
-    
-    pub fn inspect_items(&mut self) -> Vec<(u8, i32)> {
+}
+
+/// What happens to a worry level after the monkey inspected an item and got bored with it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Relief {
+    /// Worry drops to a k-th of its value
+    Divide(u64),
+    /// Worry stays as it is but is kept modulo the LCM of all tests, which none of them can tell apart
+    ModuloLcm,
+    None,
+}
+
+impl Relief {
+    pub fn apply<T: Worry>(&self, worry: T, lcm: T) -> Result<T, String> {
+        match self {
+            Relief::Divide(k) => {
+                let k = T::try_from(*k).map_err(|_| format!("{} does not fit the worry level type", k))?;
+                worry.checked_div(k).ok_or(format!("Cannot divide {} by {}", worry, k))
+            }
+            Relief::ModuloLcm => worry.checked_rem(lcm).ok_or(format!("Cannot take {} modulo {}", worry, lcm)),
+            Relief::None => Ok(worry),
+        }
+    }
+}
+
+impl<T: Worry> Monkey<T> {
+    /// Inspects and throws away all the items, returns where each of them goes
+    pub fn inspect_items(&mut self, relieve: impl Fn(T) -> Result<T, String>) -> Result<Vec<(usize, T)>, String> {
         let mut outbox = Vec::new();
 
-        while self.items.len() > 0 {
-            let item = self.items.pop_front().unwrap();
+        while let Some(item) = self.items.pop_front() {
             self.score += 1;
-            
-            let v2 = self.operation.apply(item);
-            let v3 = v2 / 3;
-            let next_monkey = if v3 % self.test == 0 { self.next_positive } else { self.next_negative };
-            
-            outbox.push((next_monkey, v3));
+
+            let worry = self.operation.evaluate(item).and_then(&relieve).map_err(|e| format!("Monkey {}: {}", self.id, e))?;
+            let divisible = worry.checked_rem(self.test) == Some(T::zero());
+            let next_monkey = if divisible { self.next_positive } else { self.next_negative };
+
+            outbox.push((next_monkey, worry));
         }
 
-        outbox
+        Ok(outbox)
     }
 }
 
-pub fn process(input: String) {
-    let mut monkeys : Vec<Monkey> = input
-        .split("\n\n")
-        .map(Monkey::parse)
-        .map(Option::unwrap)
-        .collect();
-    
-    dbg!(&monkeys);
-    
-    for _round in 0..20 {
-        for i in 0..monkeys.len() {
-            let outbox = monkeys.get_mut(i).unwrap().inspect_items();
+/// Where each item goes, the inspections per monkey of every round before it comes back to a
+/// state it was already in at the start of a round, and from which round on it repeats
+struct ItemHistory {
+    /// Inspections per monkey summed over the first `r` rounds at index `r`
+    prefix: Vec<Vec<u64>>,
+    /// First round of the cycle and its length, `None` when the history stops before repeating
+    cycle: Option<(usize, usize)>,
+}
+
+impl ItemHistory {
+    fn inspections(&self, rounds: u64) -> Vec<u64> {
+        if rounds < self.prefix.len() as u64 {
+            return self.prefix[rounds as usize].clone();
+        }
+        let (start, length) = self.cycle.expect("The history should cover all rounds or repeat");
+        let cycles = (rounds - start as u64) / length as u64;
+        let rest = (rounds - start as u64) as usize % length;
+        (0..self.prefix[0].len())
+            .map(|m| {
+                let per_cycle = self.prefix[start + length][m] - self.prefix[start][m];
+                self.prefix[start + rest][m] + cycles * per_cycle
+            })
+            .collect()
+    }
+}
+
+/// The monkeys passing items around with worry levels kept in `T`
+pub(crate) struct KeepAway<T> {
+    pub monkeys: Vec<Monkey<T>>,
+    pub relief: Relief,
+    /// Least common multiple of all the tests
+    lcm: T,
+}
+
+impl<T: Worry> KeepAway<T> {
+    pub fn new(monkeys: Vec<Monkey<T>>, relief: Relief) -> Result<KeepAway<T>, String> {
+        let mut lcm = T::try_from(1).map_err(|_| "1 does not fit the worry level type".to_owned())?;
+        for monkey in &monkeys {
+            lcm = lcm
+                .checked_div(gcd(lcm, monkey.test))
+                .and_then(|l| l.checked_mul(monkey.test))
+                .ok_or(format!("LCM of the tests overflows at monkey {}", monkey.id))?;
+        }
+        Ok(KeepAway { monkeys, relief, lcm })
+    }
+
+    pub fn parse(input: &str, relief: Relief) -> Result<KeepAway<T>, String> {
+        KeepAway::new(Monkey::parse_all(input).map_err(|e| e.to_string())?, relief)
+    }
+
+    /// Plays one round and returns how many items each monkey inspected in it
+    pub fn round(&mut self) -> Result<Vec<u64>, String> {
+        let before: Vec<u64> = self.monkeys.iter().map(|m| m.score).collect();
+        for i in 0..self.monkeys.len() {
+            let (relief, lcm) = (self.relief, self.lcm);
+            let outbox = self.monkeys[i].inspect_items(|worry| relief.apply(worry, lcm))?;
             for (id, item) in outbox {
-                monkeys.get_mut(id as usize).unwrap().items.push_back(item)
+                self.monkeys[id].items.push_back(item)
+            }
+        }
+        Ok(self.monkeys.iter().zip(before).map(|(m, b)| m.score - b).collect())
+    }
+
+    /// Plays `rounds` rounds and returns the inspections per monkey of every round
+    pub fn run(&mut self, rounds: usize) -> Result<Vec<Vec<u64>>, String> {
+        (0..rounds).map(|_| self.round()).collect()
+    }
+
+    /// Follows one item through a whole round starting at `monkey`, it keeps being thrown
+    /// within the round as long as it goes to monkeys whose turn is still to come
+    fn item_round(&self, monkey: usize, worry: T, inspections: &mut [u64]) -> Result<(usize, T), String> {
+        let (mut monkey, mut worry) = (monkey, worry);
+        loop {
+            let m = &self.monkeys[monkey];
+            inspections[monkey] += 1;
+            worry = m.operation.evaluate(worry).and_then(|w| self.relief.apply(w, self.lcm)).map_err(|e| format!("Monkey {}: {}", monkey, e))?;
+            let next = if worry.checked_rem(m.test) == Some(T::zero()) { m.next_positive } else { m.next_negative };
+            if next < monkey {
+                return Ok((next, worry));
             }
+            monkey = next;
+        }
+    }
+
+    fn item_history(&self, monkey: usize, worry: T, rounds: u64) -> Result<ItemHistory, String>
+    where T: Hash + Eq {
+        let mut seen = HashMap::new();
+        let mut prefix = vec![vec![0; self.monkeys.len()]];
+        let mut state = (monkey, worry);
+
+        while ((prefix.len() - 1) as u64) < rounds {
+            let round = prefix.len() - 1;
+            if let Some(start) = seen.insert(state, round) {
+                return Ok(ItemHistory { prefix, cycle: Some((start, round - start)) });
+            }
+            let mut inspections = prefix[round].clone();
+            state = self.item_round(state.0, state.1, &mut inspections)?;
+            prefix.push(inspections);
+        }
+        Ok(ItemHistory { prefix, cycle: None })
+    }
+
+    /// Inspections per monkey after `rounds` rounds from the current state, without playing them all.
+    /// Items never influence each other, and with worry kept modulo the LCM every item eventually
+    /// repeats a state it had at the start of an earlier round, so only the rounds up to that are played.
+    pub fn fast_forward(&self, rounds: u64) -> Result<Vec<u64>, String>
+    where T: Hash + Eq {
+        if self.relief != Relief::ModuloLcm {
+            return Err(format!("Items only repeat with {:?} relief, not {:?}", Relief::ModuloLcm, self.relief));
+        }
+
+        let mut totals: Vec<u64> = self.monkeys.iter().map(|m| m.score).collect();
+        for monkey in &self.monkeys {
+            for item in &monkey.items {
+                let history = self.item_history(monkey.id, *item, rounds)?;
+                for (total, n) in totals.iter_mut().zip(history.inspections(rounds)) {
+                    *total = total.checked_add(n).ok_or("Inspection count overflows")?;
+                }
+            }
+        }
+        Ok(totals)
+    }
+
+    /// Total inspections of the two most active monkeys multiplied
+    pub fn monkey_business(&self) -> u64 {
+        self.monkeys.iter().map(|m| m.score).sorted().rev().take(2).product()
+    }
+}
+
+fn part1(input: String) -> u64 {
+    let mut game = KeepAway::<u64>::parse(&input, Relief::Divide(3)).unwrap();
+    game.run(20).unwrap();
+    game.monkey_business()
+}
+
+fn part2(input: String) -> u64 {
+    let mut game = KeepAway::<u64>::parse(&input, Relief::ModuloLcm).unwrap();
+    game.run(10_000).unwrap();
+    game.monkey_business()
+}
+
+/// Pass a number of rounds as second argument to fast forward that far instead
+pub fn process(input: String) {
+    if let Some(rounds) = env::args().nth(2) {
+        let game = KeepAway::<u64>::parse(&input, Relief::ModuloLcm).unwrap();
+        let mut totals = game.fast_forward(rounds.parse().unwrap()).unwrap();
+        println!("Inspections after {} rounds: {:?}", rounds, totals);
+        totals.sort();
+        println!("Level of monkey business: {}", totals.iter().rev().take(2).map(|t| *t as u128).product::<u128>());
+        return;
+    }
+
+    let result = part2(input);
+    println!("Level of monkey business: {}", result);
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_parse_expr() {
+        assert_eq!(
+            Expr::parse("old * 19"),
+            Ok(Expr::Binary(Box::new(Expr::Old), BinOp::Multiply, Box::new(Expr::Number(19))))
+        );
+        assert_eq!(Expr::parse("old + old").unwrap().evaluate(7), Ok(14));
+        assert_eq!(Expr::parse("old - 3").unwrap().evaluate(7), Ok(4));
+        assert_eq!(Expr::parse("2 + old * 3").unwrap().evaluate(7), Ok(23));
+        assert_eq!(Expr::parse("(2 + old) * 3").unwrap().evaluate(7), Ok(27));
+        assert_eq!(Expr::parse(" ( old*(old - 1) ) - 10 - 2 ").unwrap().evaluate(7), Ok(30));
+        assert!(Expr::parse("old / 2").is_err());
+        assert!(Expr::parse("(old + 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_monkeys() {
+        let monkeys = Monkey::<i32>::parse_all(include_str!("../data/day11_dry.txt")).unwrap();
+        assert_eq!(monkeys.len(), 4);
+        assert_eq!(monkeys[0].items.iter().copied().collect::<Vec<_>>(), vec![79, 98]);
+        assert_eq!(monkeys[2].operation, Expr::parse("old * old").unwrap());
+        assert_eq!((monkeys[3].test, monkeys[3].next_positive, monkeys[3].next_negative), (17, 0, 1));
+    }
+
+    #[test]
+    fn test_parse_whitespace() {
+        let input = "\r\n\r\nMonkey 0:\r\n Starting items:\r\n  Operation: new = old+1  \r\n\r\n\
+            Test: divisible by 2\r\nIf true: throw to monkey 1\r\n If false: throw to monkey 1\r\n\r\n\r\n\
+            Monkey 1:\n  Starting items: 1,2 ,3\n  Operation: new = old * (old - 1)\n  Test: divisible by 3\n\
+            If true: throw to monkey 0\n  If false: throw to monkey 0\n\n";
+        let monkeys = Monkey::<u64>::parse_all(input).unwrap();
+        assert!(monkeys[0].items.is_empty());
+        assert_eq!(monkeys[1].items.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(monkeys[1].operation.evaluate(4), Ok(12));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let monkey = |operation: &str, test: &str, targets: (usize, usize)| format!(
+            "Monkey 0:\n  Starting items: 5\n  Operation: new = {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+            operation, test, targets.0, targets.1,
+        );
+        let error = |input: &str| Monkey::<i32>::parse_all(input).unwrap_err();
+
+        let other = monkey("old", "5", (0, 0)).replace("Monkey 0", "Monkey 1");
+        assert!(Monkey::<i32>::parse_all(&(monkey("old", "3", (1, 1)) + "\n" + &other)).is_ok());
+
+        let e = error(&(monkey("old", "3", (1, 2)) + "\n" + &other));
+        assert_eq!((e.monkey, e.field), (0, Field::IfFalse));
+        assert_eq!(e.to_string(), "Monkey 0, If false: cannot throw to monkey 2");
+
+        let e = error(&monkey("old / 2", "3", (1, 1)));
+        assert_eq!((e.monkey, e.field), (0, Field::Operation));
+        let e = error(&monkey("old", "0", (1, 1)));
+        assert_eq!((e.monkey, e.field), (0, Field::Test));
+        let e = error(&monkey("old", "3", (1, 1)).replace("5", "99999999999"));
+        assert_eq!(e.to_string(), "Monkey 0, Starting items: 99999999999 does not fit the worry level type");
+
+        // Fields in the wrong order name the field that was expected
+        let swapped = monkey("old", "3", (1, 1)).replace("Test", "TMP").replace("Operation", "Test").replace("TMP", "Operation");
+        let e = error(&swapped);
+        assert_eq!((e.monkey, e.field), (0, Field::Operation));
+        assert_eq!(e.to_string(), "Monkey 0, Operation: cannot read `Test: new = old`");
+
+        let e = error(&(monkey("old", "3", (1, 1)) + "\n" + &other.replace("Monkey 1", "Monkey 7")));
+        assert_eq!((e.monkey, e.field), (1, Field::Header));
+    }
+
+    #[test]
+    fn test_inspections_per_round() {
+        let input = include_str!("../data/day11_dry.txt");
+
+        let mut game = KeepAway::<u64>::parse(input, Relief::ModuloLcm).unwrap();
+        assert_eq!(game.lcm, 23 * 19 * 13 * 17);
+        let rounds = game.run(20).unwrap();
+        assert_eq!(rounds[0], vec![2, 4, 3, 6]);
+        assert_eq!(game.monkeys.iter().map(|m| m.score).collect::<Vec<_>>(), vec![99, 97, 8, 103]);
+        let totals: Vec<u64> = (0..4).map(|m| rounds.iter().map(|r| r[m]).sum()).collect();
+        assert_eq!(totals, vec![99, 97, 8, 103]);
+
+        let mut game = KeepAway::<i32>::parse(input, Relief::Divide(3)).unwrap();
+        game.run(20).unwrap();
+        assert_eq!(game.monkeys.iter().map(|m| m.score).collect::<Vec<_>>(), vec![101, 95, 7, 105]);
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        let input = include_str!("../data/day11_dry.txt");
+        let game = KeepAway::<u64>::parse(input, Relief::ModuloLcm).unwrap();
+
+        for rounds in [0, 1, 20, 1000, 10_000] {
+            let mut played = KeepAway::<u64>::parse(input, Relief::ModuloLcm).unwrap();
+            played.run(rounds).unwrap();
+            let expected: Vec<u64> = played.monkeys.iter().map(|m| m.score).collect();
+            assert_eq!(game.fast_forward(rounds as u64).unwrap(), expected, "after {} rounds", rounds);
         }
+
+        // Fast forwarding from the middle of a game continues from the current state
+        let mut played = KeepAway::<u64>::parse(input, Relief::ModuloLcm).unwrap();
+        played.run(500).unwrap();
+        assert_eq!(played.fast_forward(9500).unwrap(), game.fast_forward(10_000).unwrap());
+
+        // Cycles are found long before, so doubling the rounds past them roughly doubles the counts
+        let totals = game.fast_forward(1_000_000_000_000).unwrap();
+        let doubled = game.fast_forward(2_000_000_000_000).unwrap();
+        for (t, d) in totals.iter().zip(doubled) {
+            assert!(d.abs_diff(2 * t) < 100, "{} and {}", t, d);
+        }
+        assert!(totals[0] > 1_000_000_000_000);
+
+        let game = KeepAway::<u64>::parse(input, Relief::Divide(3)).unwrap();
+        assert!(game.fast_forward(20).is_err());
     }
-    
-    dbg!(&monkeys);
-    
-    let v: &u32 = &monkeys.iter().map(|m| m.score).sorted().rev().take(2).product();
-    dbg!(v);
-    //println!("Sum of two top monkeys activity is: {}", v);
-}
\ No newline at end of file
+
+    #[test]
+    fn test_overflow_without_relief() {
+        let input = include_str!("../data/day11_dry.txt");
+        let mut game = KeepAway::<u64>::parse(input, Relief::None).unwrap();
+        let error = game.run(20).unwrap_err();
+        assert!(error.contains("overflows"), "{}", error);
+
+        let mut game = KeepAway::<u128>::parse(input, Relief::None).unwrap();
+        assert!(game.run(3).is_ok());
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day11_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 10605);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day11_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 2713310158);
+    }
+
+    #[test]
+    fn test_evaluate_overflow() {
+        let square = Expr::parse("old * old").unwrap();
+        assert_eq!(square.evaluate(65536i32), Err("65536 * 65536 overflows".to_owned()));
+        assert_eq!(square.evaluate(65536i64), Ok(1 << 32));
+        assert_eq!(Expr::parse("old - 5").unwrap().evaluate(3u64), Err("3 - 5 overflows".to_owned()));
+        assert!(Expr::parse("old + 3000000000").unwrap().evaluate(1i32).unwrap_err().contains("does not fit"));
+    }
+}
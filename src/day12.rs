@@ -1,4 +1,13 @@
-use std::collections::{HashMap, LinkedList};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, LinkedList};
+use std::collections::hash_map::Entry;
+use std::env;
+use std::path::Path;
+use crate::image::{Color, Image};
+
+/// How many image pixels wide and high a field is when written to a file
+const SCALE: usize = 6;
+const PATH: Color = [220, 30, 30];
 
 #[derive(Debug)]
 struct Field {
@@ -40,6 +49,56 @@ impl Field {
     }
 }
 
+const ORTHOGONAL: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const DIAGONAL: [(i32, i32); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+/// Which steps are allowed between neighbouring fields and what they cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Movement {
+    pub max_climb: u8,
+    pub max_descent: u8,
+    pub diagonals: bool,
+    /// Cost of every step
+    pub step_cost: u32,
+    /// Cost per level of elevation gained in a step
+    pub climb_cost: u32,
+    /// Cost per level of elevation lost in a step
+    pub descent_cost: u32,
+}
+
+impl Movement {
+    /// Climb at most one level, go down any amount, no diagonals, every step costs the same
+    pub const PUZZLE: Movement = Movement {
+        max_climb: 1,
+        max_descent: 25,
+        diagonals: false,
+        step_cost: 1,
+        climb_cost: 0,
+        descent_cost: 0,
+    };
+
+    pub fn allows(&self, from: &Field, to: &Field) -> bool {
+        if to.altitude >= from.altitude {
+            to.altitude - from.altitude <= self.max_climb
+        } else {
+            from.altitude - to.altitude <= self.max_descent
+        }
+    }
+
+    pub fn cost(&self, from: &Field, to: &Field) -> u32 {
+        let climb = to.altitude.saturating_sub(from.altitude) as u32;
+        let descent = from.altitude.saturating_sub(to.altitude) as u32;
+        self.step_cost + self.climb_cost * climb + self.descent_cost * descent
+    }
+
+    /// Never more than the cost of the cheapest way from `from` to `to`, as A* needs
+    pub fn estimate(&self, from: &Field, to: &Field) -> u32 {
+        let (dx, dy) = (from.x.abs_diff(to.x) as u32, from.y.abs_diff(to.y) as u32);
+        let steps = if self.diagonals { dx.max(dy) } else { dx + dy };
+        self.step_cost * steps + self.cost(from, to) - self.step_cost
+    }
+}
+
 #[derive(Debug)]
 struct Map {
     width: usize,
@@ -77,14 +136,18 @@ impl Map {
         self.get_mut(item.x, item.y)
     }
     
-    pub fn successors(&self, x: usize, y: usize) -> Vec<&Field> {
+    /// The fields one step can lead to, or with `reversed` the fields a step to here can come from
+    pub fn successors(&self, x: usize, y: usize, movement: &Movement, reversed: bool) -> Vec<&Field> {
         let this = self.get(x, y);
-        
-        vec![(x as i32 - 1, y as i32), (x as i32 + 1, y as i32), (x as i32, y as i32 - 1), (x as i32, y as i32 + 1)]
+        let diagonals: &[(i32, i32)] = if movement.diagonals { &DIAGONAL } else { &[] };
+
+        ORTHOGONAL
             .iter()
+            .chain(diagonals)
+            .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
             .filter(|(x, y)| *x >= 0 && *y >= 0 && *x < self.width as i32 && *y < self.height as i32)
-            .map(|(x, y)| self.get(*x as usize, *y as usize))
-            .filter(|i| i.altitude <= this.altitude + 1)
+            .map(|(x, y)| self.get(x as usize, y as usize))
+            .filter(|i| if reversed { movement.allows(i, this) } else { movement.allows(this, i) })
             .collect()
     }
     
@@ -96,46 +159,267 @@ impl Map {
         self.fields.iter().flatten().find(|i| i.is_exit).unwrap()
     }
     
-    // Assume that all cells are initialised to u32::MAX except start which is initialised to 0
-    pub fn distance_to(&self, start: &Field, end: &Field) -> u32 {
+    /// Breadth first search from `start` until the first field where `is_goal` holds, remembering
+    /// where every field was first reached from and walking those back from the goal.
+    /// With `reversed` the search walks the steps backwards, so the path found leads from the goal to `start`.
+    /// Finds the path with the fewest steps whatever the costs of `movement` are.
+    pub fn search(&self, start: &Field, movement: &Movement, reversed: bool, is_goal: impl Fn(&Field) -> bool) -> Option<Vec<(usize, usize)>> {
         let mut queue = LinkedList::new();
-        let mut distances: HashMap<(usize, usize), u32> = HashMap::new();
-        
-        distances.insert((start.x, start.y), 0);
+        let mut predecessors: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+
+        predecessors.insert((start.x, start.y), (start.x, start.y));
         queue.push_back(start);
-        
-        while queue.len() > 0 {
-            let item = queue.pop_front().unwrap();
-            let distance_from_item_to_next = distances.get(&(item.x, item.y)).unwrap() + 1;
-
-            for next in self.successors(item.x, item.y) {
-                let next_distance = *distances.get(&(next.x, next.y)).unwrap_or(&u32::MAX);
-                
-                if next_distance > distance_from_item_to_next {
-                    // We have a better way to this cell, need to update it.
-                    distances.insert((next.x, next.y), distance_from_item_to_next);
-                    
-                    // Since we have a better way for this item, we should re-calculate all the paths from it as well.
+
+        let goal = loop {
+            let item = queue.pop_front()?;
+            if is_goal(item) {
+                break item;
+            }
+            for next in self.successors(item.x, item.y, movement, reversed) {
+                if let Entry::Vacant(entry) = predecessors.entry((next.x, next.y)) {
+                    entry.insert((item.x, item.y));
                     queue.push_back(next);
                 }
             }
+        };
+
+        let mut path = vec![(goal.x, goal.y)];
+        while path.last() != Some(&(start.x, start.y)) {
+            path.push(predecessors[path.last().unwrap()]);
+        }
+        if !reversed {
+            path.reverse();
+        }
+        Some(path)
+    }
+
+    pub fn shortest_path(&self, start: &Field, end: &Field) -> Option<Vec<(usize, usize)>> {
+        self.search(start, &Movement::PUZZLE, false, |f| f.x == end.x && f.y == end.y)
+    }
+
+    /// A* from `start` to `end` with the costs of `movement`, returns the total cost and the path
+    pub fn cheapest_path(&self, start: &Field, end: &Field, movement: &Movement) -> Option<(u32, Vec<(usize, usize)>)> {
+        let mut queue = BinaryHeap::new();
+        let mut costs: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut predecessors: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+
+        costs.insert((start.x, start.y), 0);
+        queue.push(Reverse((movement.estimate(start, end), 0, start.x, start.y)));
+
+        while let Some(Reverse((_, cost, x, y))) = queue.pop() {
+            if (x, y) == (end.x, end.y) {
+                let mut path = vec![(x, y)];
+                while let Some(previous) = predecessors.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some((cost, path));
+            }
+            if cost > costs[&(x, y)] {
+                // Already reached more cheaply since this was queued
+                continue;
+            }
+
+            let item = self.get(x, y);
+            for next in self.successors(x, y, movement, false) {
+                let next_cost = cost + movement.cost(item, next);
+                if costs.get(&(next.x, next.y)).is_none_or(|c| next_cost < *c) {
+                    costs.insert((next.x, next.y), next_cost);
+                    predecessors.insert((next.x, next.y), (x, y));
+                    queue.push(Reverse((next_cost + movement.estimate(next, end), next_cost, next.x, next.y)));
+                }
+            }
         }
-        
-        *distances.get(&(end.x, end.y)).unwrap_or(&u32::MAX)
+        None
+    }
+
+    pub fn distance_to(&self, start: &Field, end: &Field) -> u32 {
+        self.shortest_path(start, end).map_or(u32::MAX, |path| path.len() as u32 - 1)
+    }
+
+    /// The path drawn with arrows towards the next step and `E` at its end like in the puzzle,
+    /// every other field is `.`
+    pub fn render_path(&self, path: &[(usize, usize)]) -> String {
+        let mut grid = vec![vec!['.'; self.width]; self.height];
+        for step in path.windows(2) {
+            let ((x, y), (next_x, next_y)) = (step[0], step[1]);
+            grid[y][x] = match (next_x as i32 - x as i32, next_y as i32 - y as i32) {
+                (1, 0) => '>',
+                (-1, 0) => '<',
+                (0, 1) => 'v',
+                (0, -1) => '^',
+                _ => '*',
+            };
+        }
+        if let Some((x, y)) = path.last() {
+            grid[*y][*x] = 'E';
+        }
+        grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
     }
+
+    /// Elevation from dark green at `a` to white at `z` with the path on top in red
+    pub fn image(&self, path: &[(usize, usize)]) -> Image {
+        let mut pixels: Vec<Color> = self.fields
+            .iter()
+            .flatten()
+            .map(|f| {
+                let t = f.altitude as u32;
+                [(t * 255 / 25) as u8, (60 + t * 195 / 25) as u8, (t * 255 / 25) as u8]
+            })
+            .collect();
+        for (x, y) in path {
+            pixels[y * self.width + x] = PATH;
+        }
+        Image { width: self.width, height: self.height, pixels }.scaled(SCALE)
+    }
+}
+
+fn part1(input: String) -> usize {
+    let map = Map::parse(&input);
+    let path = map.shortest_path(map.find_start(), map.find_exit()).expect("There should be a path from S to E");
+    path.len() - 1
+}
+
+/// Searching backwards from E finds the closest of all the lowest fields in one go
+fn part2(input: String) -> usize {
+    let map = Map::parse(&input);
+    let path = map.search(map.find_exit(), &Movement::PUZZLE, true, |f| f.altitude == 0).expect("There should be a path from an a to E");
+    path.len() - 1
 }
 
 pub fn process(input: String) {
     let map = Map::parse(&input);
     let exit = map.find_exit();
-    
-    let min_distance = map.fields
-        .iter()
-        .flatten()
-        .filter(|i| i.altitude == 0)
-        .map(|i| map.distance_to(i, exit))
-        .min()
-        .unwrap();
-    
-    println!("Minimal distance: {}", min_distance)
+
+    let min_distance = part2(input);
+    println!("Minimal distance: {}", min_distance);
+
+    if let Some(image) = env::args().nth(2) {
+        let path = map.shortest_path(map.find_start(), exit).expect("There should be a path from S to E");
+        println!("{}", map.render_path(&path));
+        map.image(&path).save(Path::new(&image)).expect("Could not write the image");
+        println!("Wrote the map to {}", image);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_shortest_path() {
+        let map = Map::parse(include_str!("../data/day12_dry.txt"));
+        let path = map.shortest_path(map.find_start(), map.find_exit()).unwrap();
+        assert_eq!(path.len(), 32);
+        assert_eq!(map.distance_to(map.find_start(), map.find_exit()), 31);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(5, 2)));
+        // Every step moves to a neighbour climbing at most one
+        for step in path.windows(2) {
+            let ((x, y), (nx, ny)) = (step[0], step[1]);
+            assert_eq!(x.abs_diff(nx) + y.abs_diff(ny), 1);
+            assert!(map.get(nx, ny).altitude <= map.get(x, y).altitude + 1);
+        }
+
+        // E is too high to be reached from b
+        let map = Map::parse("Sz\nbE");
+        assert_eq!(map.shortest_path(map.find_start(), map.find_exit()), None);
+        assert_eq!(map.distance_to(map.find_start(), map.find_exit()), u32::MAX);
+    }
+
+    #[test]
+    fn test_reversed_search() {
+        let map = Map::parse(include_str!("../data/day12_dry.txt"));
+        let path = map.search(map.find_exit(), &Movement::PUZZLE, true, |f| f.altitude == 0).unwrap();
+        assert_eq!(path.len(), 30);
+        assert_eq!(map.get(path[0].0, path[0].1).altitude, 0);
+        assert_eq!(path.last(), Some(&(5, 2)));
+        for step in path.windows(2) {
+            let ((x, y), (nx, ny)) = (step[0], step[1]);
+            assert!(map.get(nx, ny).altitude <= map.get(x, y).altitude + 1);
+        }
+
+        // Same as the shortest of the searches from every a
+        let from_every_a = map.fields
+            .iter()
+            .flatten()
+            .filter(|f| f.altitude == 0)
+            .map(|f| map.distance_to(f, map.find_exit()))
+            .min();
+        assert_eq!(from_every_a, Some(29));
+    }
+
+    #[test]
+    fn test_cheapest_path() {
+        let map = Map::parse(include_str!("../data/day12_dry.txt"));
+        let (start, exit) = (map.find_start(), map.find_exit());
+
+        let (cost, path) = map.cheapest_path(start, exit, &Movement::PUZZLE).unwrap();
+        assert_eq!((cost, path.len()), (31, 32));
+
+        // Diagonal steps cut corners
+        let diagonals = Movement { diagonals: true, ..Movement::PUZZLE };
+        let (cost, path) = map.cheapest_path(start, exit, &diagonals).unwrap();
+        assert_eq!(cost as usize, path.len() - 1);
+        assert!(cost < 31);
+        assert_eq!(map.search(start, &diagonals, false, |f| f.is_exit).unwrap().len(), path.len());
+
+        // Climbing is all that counts, and there is a way up without ever going down
+        let least_climbing = Movement { step_cost: 0, climb_cost: 1, ..Movement::PUZZLE };
+        assert_eq!(map.cheapest_path(start, exit, &least_climbing).unwrap().0, 25);
+        let never_down = Movement { max_descent: 0, ..Movement::PUZZLE };
+        let (cost, path) = map.cheapest_path(start, exit, &never_down).unwrap();
+        for step in path.windows(2) {
+            assert!(map.get(step[0].0, step[0].1).altitude <= map.get(step[1].0, step[1].1).altitude);
+        }
+        assert!(cost >= 31);
+
+        // Penalising steep steps, the reported cost is what the steps of the path add up to
+        let gentle = Movement { max_climb: 3, climb_cost: 4, descent_cost: 2, ..Movement::PUZZLE };
+        let (cost, path) = map.cheapest_path(start, exit, &gentle).unwrap();
+        let total: u32 = path.windows(2).map(|s| gentle.cost(map.get(s[0].0, s[0].1), map.get(s[1].0, s[1].1))).sum();
+        assert_eq!(cost, total);
+
+        let stuck = Movement { max_climb: 0, ..Movement::PUZZLE };
+        assert_eq!(map.cheapest_path(start, exit, &stuck), None);
+    }
+
+    #[test]
+    fn test_render_path() {
+        let map = Map::parse(include_str!("../data/day12_dry.txt"));
+        let path = map.shortest_path(map.find_start(), map.find_exit()).unwrap();
+        // The puzzle draws another of the shortest paths, which only differs in the first steps
+        assert_eq!(map.render_path(&path), "\
+>>vv<<<<
+..vvv<<^
+..vv>E^^
+..v>>>^^
+..>>>>>^");
+        assert_eq!(map.render_path(&[(0, 0), (0, 1), (1, 1), (1, 0)]), "vE......\n>^......\n........\n........\n........");
+    }
+
+    #[test]
+    fn test_image() {
+        let map = Map::parse(include_str!("../data/day12_dry.txt"));
+        let image = map.image(&[(1, 0)]);
+        assert_eq!((image.width, image.height), (8 * SCALE, 5 * SCALE));
+        assert_eq!(image.pixels[0], [0, 60, 0]);
+        assert_eq!(image.pixels[SCALE], PATH);
+        assert_eq!(image.pixels[5 * SCALE + 2 * SCALE * 8 * SCALE], [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day12_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 31);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day12_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 29);
+    }
 }
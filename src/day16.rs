@@ -1,4 +1,4 @@
-use std::collections::{HashMap, LinkedList};
+use std::collections::{BTreeMap, HashSet, LinkedList};
 use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::{tag};
@@ -7,19 +7,18 @@ use nom::combinator::map;
 use nom::IResult;
 use nom::multi::separated_list1;
 use nom::sequence::{preceded, tuple};
-use rayon::prelude::*;
 
 #[derive(Debug, Clone)]
-enum Action {
+pub(crate) enum Action {
     Move { from: String, to: String },
     OpenValve { room_name: String, flow: u32 }
 }
 
 #[derive(Debug, PartialEq)]
-struct Room {
-    name: String,
-    flow: u32,
-    tunnels: Vec<String>
+pub(crate) struct Room {
+    pub(crate) name: String,
+    pub(crate) flow: u32,
+    pub(crate) tunnels: Vec<String>
 }
 
 impl Room {
@@ -40,7 +39,7 @@ impl Room {
     pub fn actions(&self, plan: &Plan) -> Vec<Action> {
         let mut actions = Vec::new();
 
-        if self.flow > 0 && plan.is_valve_open(&self.name) {
+        if self.flow > 0 && !plan.is_valve_open(&self.name) {
             actions.push(Action::OpenValve { room_name: self.name.clone(), flow: self.flow });
         }
 
@@ -53,7 +52,7 @@ impl Room {
 }
 
 #[derive(Debug, Clone)]
-struct Plan {
+pub(crate) struct Plan {
     actions: LinkedList<Action>
 }
 
@@ -79,7 +78,7 @@ impl Plan {
     }
     
     pub fn last_room(&self) -> &str {
-        for action in &self.actions {
+        for action in self.actions.iter().rev() {
             match action {
                 Action::Move { to, .. } => return to,
                 Action::OpenValve { .. } => {}
@@ -88,30 +87,33 @@ impl Plan {
         "AA"
     }
     
-    pub fn score(&self) -> u32 { 
+    /// Each action takes a minute, a valve opened during minute `i + 1` releases pressure for the rest of the time
+    pub fn score(&self, max_time: u32) -> u32 { 
         self.actions
             .iter()
             .enumerate()
             .map(|(i, a)| {
                 match a {
                     Action::Move { .. } => 0,
-                    Action::OpenValve { flow, .. } => *flow * (29 - i as u32)
+                    Action::OpenValve { flow, .. } => *flow * (max_time - 1 - i as u32)
                 }
             })
             .sum()
     }
     
-    pub fn is_full(&self) -> bool {
-        self.actions.len() >= 30
+    pub fn is_full(&self, max_time: u32) -> bool {
+        self.actions.len() >= max_time as usize
     }
 }
 
 #[derive(Debug)]
-struct Cave {
-    rooms: Vec<Room>,
-    edges: Vec<(usize, usize)>,
-    distances: Vec<Vec<u32>>,
-    names: HashMap<String, usize>
+pub(crate) struct Cave {
+    pub(crate) rooms: Vec<Room>,
+    pub(crate) edges: Vec<(usize, usize)>,
+    pub(crate) distances: Vec<Vec<u32>>,
+    pub(crate) names: BTreeMap<String, usize>,
+    pub(crate) flow_rates: Vec<u32>,
+    pub(crate) working_valves: Vec<usize>
 }
 
 impl Cave {
@@ -120,12 +122,16 @@ impl Cave {
             rooms,
             edges: vec![],
             distances: vec![],
-            names: HashMap::new(),
+            names: BTreeMap::new(),
+            working_valves: vec![],
+            flow_rates: vec![]
         };
         
+        cave.working_valves = cave.build_working_valves();
+        cave.flow_rates = cave.build_flow_rates();
         cave.names = cave.build_names();
         cave.edges = cave.build_edges();
-        cave.distances = cave.distances_flw();
+        cave.distances = cave.build_distances_flw();
         cave
     }
     
@@ -140,10 +146,10 @@ impl Cave {
     }
     
     pub fn index_of(&self, name: &str) -> usize {
-        self.names.get(name).unwrap().clone()
+        *self.names.get(name).unwrap()
     }
     
-    pub fn build_names(&self) -> HashMap<String, usize> {
+    fn build_names(&self) -> BTreeMap<String, usize> {
         self.rooms
             .iter()
             .enumerate()
@@ -151,7 +157,7 @@ impl Cave {
             .collect()
     }
     
-    pub fn build_edges(&self) -> Vec<(usize, usize)> {
+    fn build_edges(&self) -> Vec<(usize, usize)> {
         self.rooms
             .iter()
             .flat_map(|f| 
@@ -160,16 +166,23 @@ impl Cave {
                     .map(|t| (self.index_of(&f.name), self.index_of(t))))
             .collect_vec()
     }
+
+    fn build_flow_rates(&self) -> Vec<u32> {
+        self.rooms
+            .iter()
+            .map(|r| r.flow)
+            .collect()
+    }
     
-    pub fn distances_flw(&self) -> Vec<Vec<u32>> {
+    fn build_distances_flw(&self) -> Vec<Vec<u32>> {
         let v_dim = self.rooms.len();
         let mut dist = vec![vec![u16::MAX as u32; v_dim]; v_dim];
         
         for (u, v) in &self.edges {
             dist[*u][*v] = 1  // The weight of the edge (u, v)
         }
-        for v in 0..v_dim {
-            dist[v][v] = 0;
+        for (v, row) in dist.iter_mut().enumerate() {
+            row[v] = 0;
         }
         for k in 0..v_dim {
             for i in 0..v_dim {
@@ -183,16 +196,25 @@ impl Cave {
         
         dist
     }
+
+    fn build_working_valves(&self) -> Vec<usize> {
+        self.rooms
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.flow > 0)
+            .map(|(i, _)| i)
+            .collect()
+    }
     
-    /// Assuming we have a graph G<V, E>, this solution would have a complexity E(V)^30. 
-    /// Assuming average of E(V) of ±2 this would result in 1073741824 cycles
-    pub fn build_plans(&self) -> Vec<Plan> {
+    /// Assuming we have a graph G<V, E>, this solution would have a complexity E(V)^max_time. 
+    /// Only usable for short time limits, it serves as a reference for `max_pressure`
+    pub fn enumerate_plans(&self, max_time: u32) -> Vec<Plan> {
         let mut complete = Vec::new();
         let mut incomplete = LinkedList::new();
         incomplete.push_front(Plan::new());
         
         while let Some(plan) = incomplete.pop_front() {
-            if plan.is_full() {
+            if plan.is_full(max_time) {
                 complete.push(plan);
             }
             else {
@@ -208,52 +230,43 @@ impl Cave {
         complete
     }
     
-    /// This approach assumes that we care about only valves so it permutes the non-zero valves and then constructs paths between them
-    /// Assuming graph G<V,E> this solution has complexity V! (factorial of V)
-    /// Assuming average V of 10 this would result in 3628800
-    pub fn build_plans2(&self) -> u32 {
-        let valves = self.rooms.iter().filter(|r| r.flow > 0).collect_vec();
-        let perms = valves
-            .iter()
-            .permutations(7)
-            .collect_vec();
-        
-        println!("Ranking {} permutations", perms.len());
-        let max_score = perms
-            .par_iter()
-            .map(|permutation| {
-                let mut i = 0; 
-                let mut total_flow = 0;
-                let mut prev_valve = self.index_of("AA");
-                for valve in permutation {
-                    let valve_index = self.index_of(&valve.name);
-                    let dist = self.distances[prev_valve][valve_index];
-                    i += dist + 1;
-                    prev_valve = valve_index;
-                    if i < 30 {
-                        total_flow += valve.flow * (30 - i)
-                    }
-                    else {
-                        return total_flow
-                    }
-                }
-                total_flow
-            })
-            .max()
-            .unwrap();
-        
-        max_score
+    /// Only the working valves matter, so we jump between them using the precomputed distances
+    /// and open a valve at every stop. The search ends when no other valve can be reached in time.
+    pub fn max_pressure(&self, max_time: u32) -> u32 {
+        let starting_room = self.index_of("AA");
+        let remaining = self.working_valves.iter().cloned().collect();
+        self.max_pressure_rec(max_time, starting_room, remaining)
+    }
+    
+    fn max_pressure_rec(&self, time: u32, room: usize, remaining: HashSet<usize>) -> u32 {
+        let mut best = 0u32;
+        for next_valve in &remaining {
+            let dist = self.distances[room][*next_valve];
+            if time <= dist + 1 {
+                continue;
+            }
+            
+            let mut new_remaining = remaining.clone();
+            new_remaining.remove(next_valve);
+            
+            let new_time = time - dist - 1;
+            let value = new_time * self.flow_rates[*next_valve];
+            
+            best = best.max(value + self.max_pressure_rec(new_time, *next_valve, new_remaining));
+        }
+        best
     }
 }
 
+fn part1(input: String) -> u32 {
+    let (_, cave) = Cave::parse(&input).unwrap();
+    cave.max_pressure(30)
+}
+
 pub fn process(input: String) {
-    let (_, cave) =  Cave::parse(&input).unwrap();
-    
-    let max_score: u32 = 
-        cave.build_plans2();
+    let max_score = part1(input);
     
     println!("Maximum pressure relieved: {}", max_score);
-    println!("1660 is too high")
 }
 
 
@@ -265,6 +278,22 @@ mod tests_part16 {
 
     #[test]
     fn test_parser() {
+        let (_, room) = Room::parse("Valve HH has flow rate=22; tunnel leads to valve GG").unwrap();
+        assert_eq!(room, Room { name: "HH".to_owned(), flow: 22, tunnels: vec!["GG".to_owned()] });
+    }
+
+    #[test]
+    fn test_plans_match_max_pressure() {
+        let input = include_str!("../data/day16_dry.txt");
+        let (_, cave) = Cave::parse(input).unwrap();
+        let best_plan = cave.enumerate_plans(8).iter().map(|p| p.score(8)).max().unwrap();
+        assert_eq!(best_plan, cave.max_pressure(8));
+    }
 
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day16_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 1651);
     }
-}
\ No newline at end of file
+}
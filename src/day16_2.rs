@@ -1,47 +1,8 @@
-use std::collections::{BTreeMap, HashSet, LinkedList};
+use std::collections::{HashSet, LinkedList};
 use std::time::Instant;
 use itertools::Itertools;
-use nom::branch::alt;
-use nom::bytes::complete::{tag};
-use nom::character::complete::{alpha1, newline};
-use nom::combinator::map;
-use nom::IResult;
-use nom::multi::separated_list1;
-use nom::sequence::{preceded, tuple};
 use rayon::prelude::*;
-
-#[derive(Debug)]
-struct Room {
-    name: String,
-    flow: u32,
-    tunnels: Vec<String>
-}
-
-impl Room {
-    // Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
-    pub fn parse(input: &str) -> IResult<&str, Room> {
-        map(tuple((
-            preceded(tag("Valve "), alpha1),
-            preceded(tag(" has flow rate="), nom::character::complete::u32),
-            preceded(alt((tag("; tunnels lead to valves "), tag("; tunnel leads to valve "))), 
-                     separated_list1(tag(", "), alpha1)),
-        )), |(a, f, t)| Room {
-            name: String::from(a),
-            flow: f,
-            tunnels: t.iter().map(ToString::to_string).collect_vec(),
-        })(input)
-    }
-}
-
-#[derive(Debug)]
-struct Cave {
-    rooms: Vec<Room>,
-    edges: Vec<(usize, usize)>,
-    distances: Vec<Vec<u32>>,
-    names: BTreeMap<String, usize>,
-    flow_rates: Vec<u32>,
-    working_valves: Vec<usize>
-}
+use crate::day16::{Cave, Room};
 
 struct Score {
     max: u32
@@ -59,95 +20,6 @@ impl Score {
 }
 
 impl Cave {
-    pub fn new(rooms: Vec<Room>) -> Self {
-        let mut cave = Cave {
-            rooms,
-            edges: vec![],
-            distances: vec![],
-            names: BTreeMap::new(),
-            working_valves: vec![],
-            flow_rates: vec![]
-        };
-        
-        cave.working_valves = cave.build_working_valves();
-        cave.flow_rates = cave.build_flow_rates();
-        cave.names = cave.build_names();
-        cave.edges = cave.build_edges();
-        cave.distances = cave.build_distances_flw();
-        cave
-    }
-    
-    pub fn parse(input: &str) -> IResult<&str, Cave> {
-        map(
-            separated_list1(newline, Room::parse),
-            Cave::new)(input)
-    }
-    
-    pub fn get_by_name(&self, name: &str) -> &Room {
-        self.rooms.get(self.index_of(name)).unwrap()
-    }
-    
-    pub fn index_of(&self, name: &str) -> usize {
-        self.names.get(name).unwrap().clone()
-    }
-    
-    fn build_names(&self) -> BTreeMap<String, usize> {
-        self.rooms
-            .iter()
-            .enumerate()
-            .map(|(i, r)| (r.name.clone(), i))
-            .collect()
-    }
-    
-    fn build_edges(&self) -> Vec<(usize, usize)> {
-        self.rooms
-            .iter()
-            .flat_map(|f| 
-                f.tunnels
-                    .iter()
-                    .map(|t| (self.index_of(&f.name), self.index_of(t))))
-            .collect_vec()
-    }
-
-    fn build_flow_rates(&self) -> Vec<u32> {
-        self.rooms
-            .iter()
-            .map(|r| r.flow)
-            .collect()
-    }
-    
-    fn build_distances_flw(&self) -> Vec<Vec<u32>> {
-        let v_dim = self.rooms.len();
-        let mut dist = vec![vec![u16::MAX as u32; v_dim]; v_dim];
-        
-        for (u, v) in &self.edges {
-            dist[*u][*v] = 1  // The weight of the edge (u, v)
-        }
-        for v in 0..v_dim {
-            dist[v][v] = 0;
-        }
-        for k in 0..v_dim {
-            for i in 0..v_dim {
-                for j in 0..v_dim {
-                    if dist[i][j] > dist[i][k] + dist[k][j] {
-                        dist[i][j] = dist[i][k] + dist[k][j];
-                    }
-                }
-            }
-        }
-        
-        dist
-    }
-
-    fn build_working_valves(&self) -> Vec<usize> {
-        self.rooms
-            .iter()
-            .enumerate()
-            .filter(|(_, r)| r.flow > 0)
-            .map(|(i, _)| i)
-            .collect()
-    }
-    
     pub fn build_plans(&self, max_time: u32) -> u32 {
         let mut score = Score { max: 0 };
         let mut incomplete = LinkedList::new();
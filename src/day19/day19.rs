@@ -1,272 +1,476 @@
-use regex::Regex;
+use anyhow::{anyhow, bail};
+use nom::bytes::complete::tag;
+use nom::character::complete::{alpha1, multispace0, space1};
+use nom::combinator::cut;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
+use nom::{Finish, IResult, Parser};
+use nom_supreme::error::ErrorTree;
+use nom_supreme::ParserExt;
+use rayon::prelude::*;
 use std::{
     collections::HashMap,
-    ops::{Add, Sub},
+    env,
+    ops::{Add, Index, IndexMut, Sub},
 };
 
-#[derive(Debug, Clone, Copy)]
-struct Resources {
-    ore: u32,
-    clay: u32,
-    obsidian: u32,
-    geode: u32,
-}
+/// Amount of every resource type, indexed the same way as `Blueprint::resources`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Resources(Vec<u32>);
 
 impl Resources {
+    pub fn zero(len: usize) -> Self {
+        Resources(vec![0; len])
+    }
+
     pub fn has(&self, other: &Resources) -> bool {
-        return self.ore >= other.ore
-            && self.clay >= other.clay
-            && self.obsidian >= other.obsidian
-            && self.geode >= other.geode;
+        self.0.iter().zip(&other.0).all(|(a, b)| a >= b)
+    }
+
+    pub fn with(&self, resource: usize, amount: u32) -> Self {
+        let mut result = self.clone();
+        result[resource] += amount;
+        result
     }
 }
 
-impl Default for Resources {
-    fn default() -> Self {
-        Self {
-            ore: 0,
-            clay: 0,
-            obsidian: 0,
-            geode: 0,
-        }
+impl Index<usize> for Resources {
+    type Output = u32;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
     }
 }
 
-impl Add for Resources {
+impl IndexMut<usize> for Resources {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl Add for &Resources {
     type Output = Resources;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            ore: self.ore + rhs.ore,
-            clay: self.clay + rhs.clay,
-            obsidian: self.obsidian + rhs.obsidian,
-            geode: self.geode + rhs.geode,
-        }
+        Resources(self.0.iter().zip(&rhs.0).map(|(a, b)| a + b).collect())
     }
 }
 
-impl Sub for Resources {
+impl Sub for &Resources {
     type Output = Resources;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            ore: self.ore - rhs.ore,
-            clay: self.clay - rhs.clay,
-            obsidian: self.obsidian - rhs.obsidian,
-            geode: self.geode - rhs.geode,
-        }
+        Resources(self.0.iter().zip(&rhs.0).map(|(a, b)| a - b).collect())
     }
 }
 
+/// A factory with one robot type per resource, the robot at index `i` collects `resources[i]`
+/// and costs `costs[i]`. The search maximises the amount of the `target` resource.
 #[derive(Debug)]
 struct Blueprint {
     id: u32,
-    costs: HashMap<Robot, Resources>,
+    resources: Vec<String>,
+    costs: Vec<Resources>,
+    target: usize,
+    start_robots: Resources,
 }
 
 impl Blueprint {
-    pub fn parse(input: &str) -> Vec<Blueprint> {
-        let pattern = Regex::new(
-            r"Blueprint (\d+):\s+Each ore robot costs (\d+) ore\.\s+Each clay robot costs (\d+) ore\.\s+Each obsidian robot costs (\d+) ore and (\d+) clay\.\s+Each geode robot costs (\d+) ore and (\d+) obsidian\.",
-        ).unwrap();
-
-        let blueprints = pattern
-            .captures_iter(input)
-            .filter_map(|c| {
-                Some(Blueprint {
-                    id: c.get(1)?.as_str().parse::<u32>().ok()?,
-                    costs: HashMap::from([
-                        (
-                            Robot::Ore,
-                            Resources {
-                                ore: c.get(2)?.as_str().parse::<u32>().ok()?,
-                                ..Resources::default()
-                            },
-                        ),
-                        (
-                            Robot::Clay,
-                            Resources {
-                                ore: c.get(3)?.as_str().parse::<u32>().ok()?,
-                                ..Resources::default()
-                            },
-                        ),
-                        (
-                            Robot::Obsidian,
-                            Resources {
-                                ore: c.get(4)?.as_str().parse::<u32>().ok()?,
-                                clay: c.get(5)?.as_str().parse::<u32>().ok()?,
-                                ..Resources::default()
-                            },
-                        ),
-                        (
-                            Robot::Geode,
-                            Resources {
-                                ore: c.get(6)?.as_str().parse::<u32>().ok()?,
-                                obsidian: c.get(7)?.as_str().parse::<u32>().ok()?,
-                                ..Resources::default()
-                            },
-                        ),
-                    ]),
-                })
-            })
-            .collect::<Vec<_>>();
-        blueprints
+    /// Starts with a single robot collecting the first resource, like in the puzzle
+    pub fn new(id: u32, resources: Vec<String>, costs: Vec<Resources>, target: usize) -> Self {
+        let start_robots = Resources::zero(resources.len()).with(0, 1);
+        Blueprint { id, resources, costs, target, start_robots }
+    }
+
+    pub fn with_target(self, target: usize) -> Self {
+        Blueprint { target, ..self }
+    }
+
+    pub fn with_start_robots(self, start_robots: Resources) -> Self {
+        Blueprint { start_robots, ..self }
+    }
+
+    /// e.g. "3 ore and 14 clay"
+    fn parse_cost(input: &str) -> IResult<&str, Vec<(u32, &str)>, ErrorTree<&str>> {
+        separated_list1(
+            tag(" and "),
+            separated_pair(nom::character::complete::u32, space1, alpha1),
+        )(input)
+    }
+
+    /// e.g. "Each clay robot costs ", the cost itself is parsed by `parse_cost`
+    fn parse_robot(input: &str) -> IResult<&str, &str, ErrorTree<&str>> {
+        preceded(
+            tuple((multispace0, tag("Each "))),
+            cut(terminated(alpha1, tag(" robot costs ")).context("robot")),
+        )(input)
+    }
+
+    fn parse_id(input: &str) -> IResult<&str, u32, ErrorTree<&str>> {
+        delimited(tag("Blueprint "), nom::character::complete::u32, tag(":"))
+            .context("blueprint id")
+            .parse(input)
+    }
+
+    /// Every robot type declares a resource, costs can only be paid in declared resources.
+    /// The last declared resource is the target.
+    fn from_robots(id: u32, robots: Vec<(&str, Vec<(u32, &str)>)>) -> anyhow::Result<Blueprint> {
+        let resources: Vec<String> = robots.iter().map(|(name, _)| name.to_string()).collect();
+
+        let mut costs = vec![];
+        for (name, cost) in &robots {
+            let mut resource_cost = Resources::zero(resources.len());
+            for (amount, resource) in cost {
+                let Some(index) = resources.iter().position(|r| r == resource) else {
+                    bail!("Blueprint {}: {} robot costs unknown resource {}", id, name, resource);
+                };
+                resource_cost[index] += amount;
+            }
+            costs.push(resource_cost);
+        }
+
+        Ok(Blueprint::new(id, resources, costs, robots.len() - 1))
+    }
+
+    pub fn parse(input: &str) -> anyhow::Result<Vec<Blueprint>> {
+        let mut blueprints = vec![];
+        let mut rest = input.trim_start();
+
+        while !rest.is_empty() {
+            let (after_id, id) = Blueprint::parse_id(rest)
+                .finish()
+                .map_err(|e| anyhow!("Blueprint #{}: {}", blueprints.len() + 1, e))?;
+            rest = after_id;
+
+            let mut robots = vec![];
+            loop {
+                let (after_robot, name) = match Blueprint::parse_robot(rest) {
+                    Ok(result) => result,
+                    Err(nom::Err::Error(_)) if !robots.is_empty() => break,
+                    Err(e) => bail!("Blueprint {}: {}", id, e.map(|e| e.to_string())),
+                };
+                let (after_cost, cost) = terminated(Blueprint::parse_cost, tag("."))
+                    .context("cost")
+                    .parse(after_robot)
+                    .finish()
+                    .map_err(|e| anyhow!("Blueprint {}, {} robot: {}", id, name, e))?;
+
+                robots.push((name, cost));
+                rest = after_cost;
+            }
+
+            blueprints.push(Blueprint::from_robots(id, robots)?);
+            rest = rest.trim_start();
+        }
+
+        Ok(blueprints)
     }
 
     pub fn score(&self, time_limit: u8) -> u32 {
-        let mut max_score = 0;
-
-        let max_robots = self
-            .costs
-            .values()
-            .map(|a| *a)
-            .reduce(|a, b| Resources {
-                ore: a.ore.max(b.ore),
-                clay: a.clay.max(b.clay),
-                obsidian: a.obsidian.max(b.obsidian),
-                geode: a.geode.max(b.geode),
+        self.build_order(time_limit).geodes
+    }
+
+    /// No point in making more robots than we can spend in a minute, except for the target
+    fn max_robots(&self) -> Resources {
+        let mut max_robots = Resources::zero(self.resources.len());
+        for cost in &self.costs {
+            for (max, amount) in max_robots.0.iter_mut().zip(&cost.0) {
+                *max = (*max).max(*amount);
+            }
+        }
+        max_robots[self.target] = u32::MAX;
+        max_robots
+    }
+
+    /// Robots worth trying, the target first and then in reverse order of declaration
+    fn robot_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.resources.len()).rev().filter(|r| *r != self.target).collect();
+        order.insert(0, self.target);
+        order
+    }
+
+    /// Finds the best robot build order and replays it to get the inventory over time
+    pub fn build_order(&self, time_limit: u8) -> BuildOrder {
+        let max_robots = self.max_robots();
+        let robot_order = self.robot_order();
+
+        let start = State {
+            minute: 1,
+            robots: self.start_robots.clone(),
+            storage: Resources::zero(self.resources.len()),
+        };
+
+        // Every first robot choice is searched on its own thread with its own pruning state
+        let (geodes, mut builds) = robot_order
+            .par_iter()
+            .map(|robot| match self.build(*robot, time_limit, &max_robots, &start) {
+                Some(next) => {
+                    let mut best = 0;
+                    let mut seen = HashMap::new();
+                    let (geodes, mut builds) =
+                        self.score_rec(time_limit, &mut best, &mut seen, (&max_robots, &robot_order), next.clone());
+                    builds.push((next.minute - 1, *robot));
+                    (geodes, builds)
+                }
+                None => (start.idle(self.target, time_limit), vec![]),
             })
+            .max_by_key(|(geodes, _)| *geodes)
             .unwrap();
 
-        self.score_rec(
-            time_limit,
-            &mut max_score,
-            &max_robots,
-            1,
-            Resources {
-                ore: 1,
-                ..Resources::default()
-            },
-            Resources::default(),
-        )
+        builds.reverse();
+        let inventory = self.replay(time_limit, &builds);
+        BuildOrder { geodes, builds, inventory }
     }
 
-    fn score_rec(
-        &self,
-        time_limit: u8,
-        max_score: &mut u32,
-        max_robots: &Resources,
-        minute: u8,
-        robots: Resources,
-        storage: Resources,
-    ) -> u32 {
-        Robot::unlocked(robots)
+    /// Storage at the end of every minute when the robots are built as in `builds`
+    pub fn replay(&self, time_limit: u8, builds: &[(u8, usize)]) -> Vec<Resources> {
+        let mut robots = self.start_robots.clone();
+        let mut storage = Resources::zero(self.resources.len());
+        let mut inventory = vec![];
+
+        for minute in 1..=time_limit {
+            let built = builds.iter().find(|(m, _)| *m == minute).map(|(_, r)| *r);
+            if let Some(robot) = built {
+                storage = &storage - &self.costs[robot];
+            }
+            storage = &storage + &robots;
+            if let Some(robot) = built {
+                robots[robot] += 1;
+            }
+            inventory.push(storage.clone());
+        }
+        inventory
+    }
+
+    /// e.g. "3 ore and 14 clay"
+    fn describe_cost(&self, cost: &Resources) -> String {
+        cost.0
             .iter()
-            .map(|robot| {
-                match robot {
-                    // No point in making more robots than max
-                    Robot::Ore if robots.ore >= max_robots.ore => return 0,
-                    Robot::Clay if robots.clay >= max_robots.clay => return 0,
-                    Robot::Obsidian if robots.obsidian >= max_robots.obsidian => return 0,
-                    _ => (),
-                }
+            .zip(&self.resources)
+            .filter(|(amount, _)| **amount > 0)
+            .map(|(amount, name)| format!("{} {}", amount, name))
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
 
-                let robot_cost = self.costs.get(&robot).unwrap();
-                let mut next_minute = minute;
-                let mut next_storage = storage;
+    fn robot_name(&self, robot: usize) -> String {
+        match self.resources[robot].as_str() {
+            "geode" => "geode-cracking robot".to_owned(),
+            name => format!("{}-collecting robot", name),
+        }
+    }
+
+    /// e.g. "2 ore-collecting robots collect 2 ore; you now have 4 ore."
+    fn describe_collection(&self, robot: usize, count: u32, total: u32) -> String {
+        let plural = |n: u32| if n == 1 { "" } else { "s" };
+        let verb_s = if count == 1 { "s" } else { "" };
+        match self.resources[robot].as_str() {
+            "geode" => format!(
+                "{count} {}{} crack{} {count} geode{}; you now have {total} open geode{}.\n",
+                self.robot_name(robot), plural(count), verb_s, plural(count), plural(total)
+            ),
+            name => format!(
+                "{count} {}{} collect{} {count} {name}; you now have {total} {name}.\n",
+                self.robot_name(robot), plural(count), verb_s
+            ),
+        }
+    }
 
-                while next_minute <= time_limit && !next_storage.has(robot_cost) {
-                    next_minute = next_minute + 1;
-                    next_storage = next_storage + robots;
+    /// Describes the build order minute by minute, the same way the puzzle does
+    pub fn narrate(&self, order: &BuildOrder) -> String {
+        let mut robots = self.start_robots.clone();
+        let mut out = String::new();
+
+        for (i, storage) in order.inventory.iter().enumerate() {
+            let minute = i as u8 + 1;
+            let built = order.builds.iter().find(|(m, _)| *m == minute).map(|(_, r)| *r);
+
+            out += &format!("== Minute {minute} ==\n");
+            if let Some(robot) = built {
+                let name = self.robot_name(robot);
+                let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+                out += &format!("Spend {} to start building {} {}.\n", self.describe_cost(&self.costs[robot]), article, name);
+            }
+            for robot in 0..self.resources.len() {
+                if robots[robot] > 0 {
+                    out += &self.describe_collection(robot, robots[robot], storage[robot]);
                 }
+            }
+            if let Some(robot) = built {
+                robots[robot] += 1;
+                out += &format!("The new {} is ready; you now have {} of them.\n", self.robot_name(robot), robots[robot]);
+            }
+            out += "\n";
+        }
+        out
+    }
 
-                if next_minute >= time_limit {
-                    if next_storage.geode > *max_score {
-                        *max_score = next_storage.geode;
-                        println!("{}", next_storage.geode)
-                    }
-                    return next_storage.geode;
-                } else {
-                    next_minute = next_minute + 1;
-                    next_storage = next_storage - *robot_cost + robots;
-                    let next_robots = Resources {
-                        ore: robots.ore + if let Robot::Ore = robot { 1 } else { 0 },
-                        clay: robots.clay + if let Robot::Clay = robot { 1 } else { 0 },
-                        obsidian: robots.obsidian + if let Robot::Obsidian = robot { 1 } else { 0 },
-                        geode: robots.geode + if let Robot::Geode = robot { 1 } else { 0 },
-                    };
-                    return self.score_rec(
-                        time_limit,
-                        max_score,
-                        max_robots,
-                        next_minute,
-                        next_robots,
-                        next_storage,
-                    );
+    /// Waits until `robot` is affordable and builds it, returns `None` when that is pointless
+    fn build(&self, robot: usize, time_limit: u8, max_robots: &Resources, state: &State) -> Option<State> {
+        let robots = &state.robots;
+        if robots[robot] >= max_robots[robot] {
+            return None;
+        }
+
+        // Waiting only helps when we already collect everything the robot costs
+        let robot_cost = &self.costs[robot];
+        if robot_cost.0.iter().zip(&robots.0).any(|(cost, count)| *cost > 0 && *count == 0) {
+            return None;
+        }
+
+        let mut next_minute = state.minute;
+        let mut next_storage = state.storage.clone();
+
+        while next_minute < time_limit && !next_storage.has(robot_cost) {
+            next_minute += 1;
+            next_storage = &next_storage + robots;
+        }
+
+        // A robot built in the last minute never gets to collect anything
+        if next_minute >= time_limit {
+            return None;
+        }
+
+        Some(State {
+            minute: next_minute + 1,
+            storage: &(&next_storage - robot_cost) + robots,
+            robots: robots.with(robot, 1),
+        })
+    }
+
+    /// A state is dominated when we were already at the same minute with the same robots
+    /// and at least as many resources of every kind
+    fn is_dominated(seen: &mut HashMap<(u8, Resources), Vec<Resources>>, state: &State) -> bool {
+        let storages = seen.entry((state.minute, state.robots.clone())).or_default();
+        if storages.iter().any(|s| s.has(&state.storage)) {
+            return true;
+        }
+        storages.retain(|s| !state.storage.has(s));
+        storages.push(state.storage.clone());
+        false
+    }
+
+    fn score_rec(
+        &self,
+        time_limit: u8,
+        best: &mut u32,
+        seen: &mut HashMap<(u8, Resources), Vec<Resources>>,
+        (max_robots, robot_order): (&Resources, &[usize]),
+        state: State,
+    ) -> (u32, Vec<(u8, usize)>) {
+        let idle = state.idle(self.target, time_limit);
+        *best = (*best).max(idle);
+
+        if state.upper_bound(self.target, time_limit) <= *best || Blueprint::is_dominated(seen, &state) {
+            return (idle, vec![]);
+        }
+
+        robot_order
+            .iter()
+            .map(|robot| match self.build(*robot, time_limit, max_robots, &state) {
+                Some(next) => {
+                    let minute = next.minute - 1;
+                    let (geodes, mut builds) = self.score_rec(time_limit, best, seen, (max_robots, robot_order), next);
+                    builds.push((minute, *robot));
+                    (geodes, builds)
                 }
+                None => (idle, vec![]),
             })
-            .max()
+            .max_by_key(|(geodes, _)| *geodes)
             .unwrap()
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-#[repr(u8)]
-enum Robot {
-    Ore,
-    Clay,
-    Obsidian,
-    Geode,
+/// The best amount of the target resource with the robots built in it, as `(minute, robot)`
+/// pairs, and the storage at the end of every minute
+#[derive(Debug)]
+pub struct BuildOrder {
+    geodes: u32,
+    builds: Vec<(u8, usize)>,
+    inventory: Vec<Resources>,
 }
 
-impl Robot {
-    const ALL: [Robot; 4] = [Robot::Geode, Robot::Obsidian, Robot::Clay, Robot::Ore];
+/// Robots and resources at the start of `minute`
+#[derive(Debug, Clone)]
+struct State {
+    minute: u8,
+    robots: Resources,
+    storage: Resources,
+}
 
-    pub fn unlocked(robots: Resources) -> &'static [Robot] {
-        if robots.obsidian > 0 {
-            &Robot::ALL[0..4]
-        } else if robots.clay > 0 {
-            &Robot::ALL[1..4]
-        } else {
-            &Robot::ALL[2..4]
-        }
+impl State {
+    fn remaining(&self, time_limit: u8) -> u32 {
+        (time_limit + 1 - self.minute) as u32
+    }
+
+    /// Target resources collected when we stop building robots now
+    pub fn idle(&self, target: usize, time_limit: u8) -> u32 {
+        self.storage[target] + self.robots[target] * self.remaining(time_limit)
+    }
+
+    /// Target resources we would end up with if we built a target robot in every remaining minute
+    pub fn upper_bound(&self, target: usize, time_limit: u8) -> u32 {
+        let remaining = self.remaining(time_limit);
+        self.idle(target, time_limit) + remaining * remaining.saturating_sub(1) / 2
     }
 }
 
+/// Pass a blueprint id (and optionally a time limit) after the file name to see its build order
 pub fn process(input: String) {
+    if let Some(id) = env::args().nth(2) {
+        let id: u32 = id.parse().expect("Blueprint id should be a number");
+        let time_limit: u8 = env::args().nth(3).map(|t| t.parse().unwrap()).unwrap_or(24);
+
+        let bps = Blueprint::parse(&input).unwrap();
+        let bp = bps.iter().find(|bp| bp.id == id).expect("Unknown blueprint");
+        let order = bp.build_order(time_limit);
+        print!("{}", bp.narrate(&order));
+        println!("Blueprint {}: {} {}", bp.id, order.geodes, bp.resources[bp.target]);
+        return;
+    }
+
     let result = part2(input);
 
     println!("Result: {}", result);
 }
 
 fn single_bp(input: String) -> u32 {
-    let bps = Blueprint::parse(&input);
+    let bps = Blueprint::parse(&input).unwrap();
     let bp = bps.get(2).unwrap();
-    let result = bp.score(32);
+    bp.score(32)
+}
 
-    return result;
+/// Scores the blueprints in parallel, the result keeps the order of `bps`
+fn score_all(bps: &[Blueprint], time_limit: u8) -> Vec<u32> {
+    let scores: Vec<u32> = bps.par_iter().map(|bp| bp.score(time_limit)).collect();
+
+    for (bp, score) in bps.iter().zip(&scores) {
+        println!("Blueprint {}: {} geodes", bp.id, score);
+    }
+    scores
 }
 
 fn part1(input: String) -> String {
-    let bps = Blueprint::parse(&input);
+    let bps = Blueprint::parse(&input).unwrap();
 
-    let total_score: u32 = bps
+    let total_score: u32 = score_all(&bps, 24)
         .iter()
-        .map(|bp| {
-            let score = bp.score(24);
-            println!("Blueprint {}: {} geodes", bp.id, score);
-            bp.id * score
-        })
+        .zip(&bps)
+        .map(|(score, bp)| bp.id * score)
         .sum();
 
-    return format!("Total score: {total_score}");
+    format!("Total score: {total_score}")
 }
 
 fn part2(input: String) -> String {
-    let bps = Blueprint::parse(&input);
+    let bps = Blueprint::parse(&input).unwrap();
+    let bps = &bps[..bps.len().min(3)];
 
-    let total_score: u32 = bps
+    let total_score: u32 = score_all(bps, 32)
         .iter()
-        .take(3)
-        .map(|bp| {
-            let score = bp.score(32);
-            println!("Blueprint {}: {} geodes", bp.id, score);
-            score
-        })
-        .reduce(|a, b| a * b)
-        .unwrap();
+        .product();
 
-    return format!("Total score: {total_score}");
+    format!("Total score: {total_score}")
 }
 
 #[cfg(test)]
@@ -276,21 +480,107 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        let input = "";
-        let bp = Blueprint::parse(input);
-        println!("{:?}", bp);
+        let input = include_str!("../../data/day19_dry.txt");
+        let bps = Blueprint::parse(input).unwrap();
+        assert_eq!(bps.len(), 2);
+        assert_eq!(bps[1].id, 2);
+        assert_eq!(bps[1].resources, vec!["ore", "clay", "obsidian", "geode"]);
+        assert_eq!(bps[1].costs[2], Resources(vec![3, 8, 0, 0]));
+        assert_eq!(bps[1].costs[3], Resources(vec![3, 0, 12, 0]));
+        assert_eq!(bps[1].target, 3);
+
+        let input = include_str!("../../data/day19_sharp.txt");
+        assert_eq!(Blueprint::parse(input).unwrap().len(), 30);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs lots of ore.";
+        let error = Blueprint::parse(input).unwrap_err().to_string();
+        assert!(error.starts_with("Blueprint 2, clay robot:"), "{}", error);
+        assert!(error.contains("cost"), "{}", error);
+
+        let error = Blueprint::parse("Blueprint x:").unwrap_err().to_string();
+        assert!(error.contains("blueprint id"), "{}", error);
+
+        let error = Blueprint::parse("Blueprint 3: Each ore robot costs 2 gold.").unwrap_err().to_string();
+        assert_eq!(error, "Blueprint 3: ore robot costs unknown resource gold");
+    }
+
+    /// Tries every choice in every minute, only usable for tiny factories
+    fn brute_force(bp: &Blueprint, minutes_left: u8, robots: Resources, storage: Resources) -> u32 {
+        if minutes_left == 0 {
+            return storage[bp.target];
+        }
+        let mut best = brute_force(bp, minutes_left - 1, robots.clone(), &storage + &robots);
+        for (robot, cost) in bp.costs.iter().enumerate() {
+            if storage.has(cost) {
+                let next_storage = &(&storage - cost) + &robots;
+                best = best.max(brute_force(bp, minutes_left - 1, robots.with(robot, 1), next_storage));
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_generic_factory() {
+        let input = "Blueprint 7: Each wood robot costs 2 wood. Each stone robot costs 3 wood. \
+            Each gold robot costs 1 wood and 2 stone. Each gem robot costs 2 stone and 2 gold.";
+        let bp = Blueprint::parse(input).unwrap().remove(0);
+        assert_eq!(bp.resources, vec!["wood", "stone", "gold", "gem"]);
+
+        for time_limit in 1..=12 {
+            let expected = brute_force(&bp, time_limit, bp.start_robots.clone(), Resources::zero(4));
+            assert_eq!(bp.score(time_limit), expected, "gems in {} minutes", time_limit);
+        }
+
+        // Aim for gold instead, starting with a stone robot as well
+        let bp = bp.with_target(2).with_start_robots(Resources(vec![1, 1, 0, 0]));
+        let expected = brute_force(&bp, 12, bp.start_robots.clone(), Resources::zero(4));
+        assert_eq!(bp.score(12), expected);
+        assert!(expected > 0);
+    }
+
+    #[test]
+    fn test_score_example() {
+        let input = include_str!("../../data/day19_dry.txt");
+        let bps = Blueprint::parse(input).unwrap();
+        assert_eq!(bps[0].score(24), 9);
+        assert_eq!(bps[1].score(24), 12);
+        assert_eq!(bps[0].score(32), 56);
+        assert_eq!(bps[1].score(32), 62);
+    }
+
+    #[test]
+    fn test_build_order() {
+        let input = include_str!("../../data/day19_dry.txt");
+        let bps = Blueprint::parse(input).unwrap();
+        let order = bps[0].build_order(24);
+        assert_eq!(order.geodes, 9);
+        assert_eq!(order.inventory.len(), 24);
+        assert_eq!(order.inventory.last().unwrap()[3], 9);
+        assert_eq!(order.builds.first(), Some(&(3, 1)));
+
+        let narrative = bps[0].narrate(&order);
+        assert!(narrative.starts_with("== Minute 1 ==\n1 ore-collecting robot collects 1 ore; you now have 1 ore.\n\n"));
+        assert!(narrative.contains("== Minute 3 ==\n\
+            Spend 2 ore to start building a clay-collecting robot.\n\
+            1 ore-collecting robot collects 1 ore; you now have 1 ore.\n\
+            The new clay-collecting robot is ready; you now have 1 of them.\n"));
+        assert!(narrative.ends_with("2 geode-cracking robots crack 2 geodes; you now have 9 open geodes.\n\n"));
     }
 
     #[test]
     fn test_part1() {
-        let input = "".to_owned();
+        let input = include_str!("../../data/day19_sharp.txt").to_owned();
         let result = part1(input);
         assert_eq!(result, "Total score: 1589");
     }
 
     #[test]
     fn test_part2() {
-        let input = "".to_owned();
+        let input = include_str!("../../data/day19_sharp.txt").to_owned();
         let result = part2(input);
         assert_eq!(result, "Total score: 29348");
     }
@@ -0,0 +1,189 @@
+use nom::character::complete::{i64, multispace0, newline};
+use nom::IResult;
+use nom::multi::separated_list1;
+use nom::sequence::terminated;
+
+const DECRYPTION_KEY: i64 = 811589153;
+
+/// A circular list of element ids split into buckets of roughly sqrt(n) items,
+/// so finding, removing and inserting an element only touches one bucket
+/// plus the bucket lengths instead of shifting the whole list
+struct MixingList {
+    buckets: Vec<Vec<usize>>,
+    /// Bucket index of every element id
+    bucket_of: Vec<usize>,
+    bucket_size: usize,
+}
+
+impl MixingList {
+    pub fn new(len: usize) -> MixingList {
+        let bucket_size = ((len as f64).sqrt() as usize).max(1);
+        let mut list = MixingList {
+            buckets: vec![(0..len).collect()],
+            bucket_of: vec![0; len],
+            bucket_size,
+        };
+        list.rebuild();
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.bucket_of.len()
+    }
+
+    /// Splits the elements into evenly sized buckets again
+    fn rebuild(&mut self) {
+        let items: Vec<usize> = self.buckets.iter().flatten().copied().collect();
+        self.buckets = items
+            .chunks(self.bucket_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        for (b, bucket) in self.buckets.iter().enumerate() {
+            for id in bucket {
+                self.bucket_of[*id] = b;
+            }
+        }
+    }
+
+    pub fn position(&self, id: usize) -> usize {
+        let b = self.bucket_of[id];
+        let before: usize = self.buckets[..b].iter().map(Vec::len).sum();
+        before + self.buckets[b].iter().position(|i| *i == id).unwrap()
+    }
+
+    pub fn get(&self, position: usize) -> usize {
+        let mut position = position % self.len();
+        for bucket in &self.buckets {
+            if position < bucket.len() {
+                return bucket[position];
+            }
+            position -= bucket.len();
+        }
+        unreachable!("position is always within the list")
+    }
+
+    fn remove(&mut self, id: usize) {
+        let bucket = &mut self.buckets[self.bucket_of[id]];
+        let index = bucket.iter().position(|i| *i == id).unwrap();
+        bucket.remove(index);
+    }
+
+    /// Inserts `id` so that it ends up at `position` among the other elements
+    fn insert(&mut self, id: usize, position: usize) {
+        let mut position = position;
+        let mut b = 0;
+        while b < self.buckets.len() - 1 && position > self.buckets[b].len() {
+            position -= self.buckets[b].len();
+            b += 1;
+        }
+
+        self.buckets[b].insert(position, id);
+        self.bucket_of[id] = b;
+
+        if self.buckets[b].len() > self.bucket_size * 2 {
+            self.rebuild();
+        }
+    }
+
+    /// Moves the element forward by `offset` places, wrapping around the circle
+    /// without counting the element itself
+    pub fn move_by(&mut self, id: usize, offset: i64) {
+        if self.len() < 2 {
+            return;
+        }
+        let position = self.position(id);
+        self.remove(id);
+        let target = (position as i64 + offset).rem_euclid(self.len() as i64 - 1);
+        self.insert(id, target as usize);
+    }
+}
+
+fn parse_numbers(input: &str) -> IResult<&str, Vec<i64>> {
+    terminated(separated_list1(newline, i64), multispace0)(input)
+}
+
+/// Mixes the numbers `rounds` times in their original order and returns them in the mixed order
+fn mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+    let mut list = MixingList::new(numbers.len());
+    for _ in 0..rounds {
+        for (id, number) in numbers.iter().enumerate() {
+            list.move_by(id, *number);
+        }
+    }
+    (0..numbers.len()).map(|p| numbers[list.get(p)]).collect()
+}
+
+/// Sum of the 1000th, 2000th and 3000th numbers after the value 0
+fn grove_coordinates(mixed: &[i64]) -> i64 {
+    let zero = mixed.iter().position(|n| *n == 0).expect("There should be a 0 in the file");
+    [1000, 2000, 3000]
+        .iter()
+        .map(|offset| mixed[(zero + offset) % mixed.len()])
+        .sum()
+}
+
+fn part1(input: String) -> i64 {
+    let (_, numbers) = parse_numbers(&input).unwrap();
+    grove_coordinates(&mix(&numbers, 1))
+}
+
+fn part2(input: String) -> i64 {
+    let (_, numbers) = parse_numbers(&input).unwrap();
+    let numbers: Vec<i64> = numbers.iter().map(|n| n * DECRYPTION_KEY).collect();
+    grove_coordinates(&mix(&numbers, 10))
+}
+
+pub fn process(input: String) {
+    let result = part2(input);
+    println!("Sum of grove coordinates: {}", result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    /// The list is circular, so any rotation of the expected order is the same mix
+    fn is_rotation(mixed: &[i64], expected: &[i64]) -> bool {
+        (0..mixed.len()).any(|start| mixed[start..].iter().chain(&mixed[..start]).eq(expected))
+    }
+
+    #[test]
+    fn test_mix() {
+        let numbers = vec![1, 2, -3, 3, -2, 0, 4];
+        let expected = [1, 2, -3, 4, 0, 3, -2];
+        assert!(is_rotation(&mix(&numbers, 1), &expected));
+    }
+
+    #[test]
+    fn test_mix_matches_naive() {
+        // Offsets larger than the list and long enough to trigger bucket rebuilds
+        let numbers: Vec<i64> = (0..200).map(|i| (i * 7919 % 401) - 200).collect();
+
+        let mut naive: Vec<(usize, i64)> = numbers.iter().copied().enumerate().collect();
+        for id in 0..numbers.len() {
+            let position = naive.iter().position(|(i, _)| *i == id).unwrap();
+            let item = naive.remove(position);
+            let target = (position as i64 + item.1).rem_euclid(naive.len() as i64);
+            naive.insert(target as usize, item);
+        }
+        let naive: Vec<i64> = naive.iter().map(|(_, n)| *n).collect();
+
+        assert!(is_rotation(&mix(&numbers, 1), &naive));
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day20_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 3);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day20_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 1623178306);
+    }
+}
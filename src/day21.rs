@@ -0,0 +1,325 @@
+use std::collections::{HashMap, HashSet};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{alpha1, line_ending, multispace0};
+use nom::combinator::{map, value};
+use nom::IResult;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, separated_pair, terminated, tuple};
+use crate::checked::CheckedInt;
+
+const ROOT: &str = "root";
+const HUMAN: &str = "humn";
+
+/// Integers the monkeys can calculate with
+trait MonkeyNumber: CheckedInt + From<i64> {}
+
+impl<T: CheckedInt + From<i64>> MonkeyNumber for T {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    pub fn parse(input: &str) -> IResult<&str, Operator> {
+        alt((
+            value(Operator::Add, tag("+")),
+            value(Operator::Subtract, tag("-")),
+            value(Operator::Multiply, tag("*")),
+            value(Operator::Divide, tag("/")),
+        ))(input)
+    }
+
+    /// Monkeys only deal in whole numbers, so a division has to be exact
+    pub fn apply<T: MonkeyNumber>(&self, left: T, right: T) -> Result<T, String> {
+        let overflow = || format!("{} {:?} {} overflows", left, self, right);
+        match self {
+            Operator::Add => left.checked_add(right).ok_or_else(overflow),
+            Operator::Subtract => left.checked_sub(right).ok_or_else(overflow),
+            Operator::Multiply => left.checked_mul(right).ok_or_else(overflow),
+            Operator::Divide => {
+                if right == T::from(0) {
+                    return Err(format!("{} / {} divides by zero", left, right));
+                }
+                let remainder = left.checked_rem(right).ok_or_else(overflow)?;
+                if remainder != T::from(0) {
+                    return Err(format!("{} / {} is not an integer", left, right));
+                }
+                left.checked_div(right).ok_or_else(overflow)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Job {
+    Number(i64),
+    Operation(String, Operator, String),
+}
+
+impl Job {
+    // root: pppw + sjmn
+    // dbpl: 5
+    pub fn parse(input: &str) -> IResult<&str, (String, Job)> {
+        separated_pair(
+            map(alpha1, String::from),
+            tag(": "),
+            alt((
+                map(nom::character::complete::i64, Job::Number),
+                map(
+                    tuple((alpha1, delimited(tag(" "), Operator::parse, tag(" ")), alpha1)),
+                    |(l, op, r): (&str, Operator, &str)| Job::Operation(l.to_owned(), op, r.to_owned()),
+                ),
+            )),
+        )(input)
+    }
+}
+
+/// The jobs form a DAG where every monkey waits for the monkeys in its operation
+struct Monkeys {
+    jobs: HashMap<String, Job>,
+}
+
+impl Monkeys {
+    pub fn parse(input: &str) -> IResult<&str, Monkeys> {
+        map(
+            terminated(separated_list1(line_ending, Job::parse), multispace0),
+            |jobs| Monkeys { jobs: jobs.into_iter().collect() },
+        )(input)
+    }
+
+    fn job(&self, name: &str) -> Result<&Job, String> {
+        self.jobs.get(name).ok_or(format!("Unknown monkey {}", name))
+    }
+
+    pub fn evaluate<T: MonkeyNumber>(&self, name: &str) -> Result<T, String> {
+        self.evaluate_memo(name, &mut HashMap::new(), &mut HashSet::new())
+    }
+
+    /// `visiting` holds the monkeys whose numbers are being worked out, meeting one again means a cycle
+    fn evaluate_memo<'a, T: MonkeyNumber>(
+        &'a self,
+        name: &'a str,
+        memo: &mut HashMap<&'a str, T>,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Result<T, String> {
+        if let Some(result) = memo.get(name) {
+            return Ok(*result);
+        }
+        if !visiting.insert(name) {
+            return Err(format!("{} depends on itself", name));
+        }
+        let result = match self.job(name)? {
+            Job::Number(n) => T::from(*n),
+            Job::Operation(left, op, right) => {
+                let left = self.evaluate_memo(left, memo, visiting)?;
+                let right = self.evaluate_memo(right, memo, visiting)?;
+                op.apply(left, right).map_err(|e| format!("{}: {}", name, e))?
+            }
+        };
+        visiting.remove(name);
+        memo.insert(name, result);
+        Ok(result)
+    }
+
+    /// Every monkey whose number depends on what `target` yells, `target` included
+    pub fn dependents(&self, target: &str) -> Result<HashSet<&str>, String> {
+        let (mut memo, mut visiting) = (HashMap::new(), HashSet::new());
+        for name in self.jobs.keys() {
+            self.depends_on_memo(name, target, &mut memo, &mut visiting)?;
+        }
+        Ok(memo.into_iter().filter(|(_, depends)| *depends).map(|(name, _)| name).collect())
+    }
+
+    fn depends_on_memo<'a>(
+        &'a self,
+        name: &'a str,
+        target: &str,
+        memo: &mut HashMap<&'a str, bool>,
+        visiting: &mut HashSet<&'a str>,
+    ) -> Result<bool, String> {
+        if let Some(depends) = memo.get(name) {
+            return Ok(*depends);
+        }
+        if !visiting.insert(name) {
+            return Err(format!("{} depends on itself", name));
+        }
+        let depends = match self.job(name)? {
+            Job::Number(_) => false,
+            Job::Operation(left, _, right) => {
+                // Both sides are always visited so that every monkey ends up in the memo
+                let left = self.depends_on_memo(left, target, memo, visiting)?;
+                let right = self.depends_on_memo(right, target, memo, visiting)?;
+                left || right
+            }
+        } || name == target;
+        visiting.remove(name);
+        memo.insert(name, depends);
+        Ok(depends)
+    }
+
+    /// Finds the number `humn` has to yell so that `name` yells `expected`,
+    /// by undoing the operations on the way from `name` down to `humn`
+    pub fn solve<T: MonkeyNumber>(&self, name: &str, expected: T) -> Result<T, String> {
+        self.solve_with(name, expected, &self.dependents(HUMAN)?)
+    }
+
+    /// `human` holds the monkeys depending on `humn`
+    fn solve_with<T: MonkeyNumber>(&self, name: &str, expected: T, human: &HashSet<&str>) -> Result<T, String> {
+        if name == HUMAN {
+            return Ok(expected);
+        }
+        let Job::Operation(left, op, right) = self.job(name)? else {
+            return Err(format!("{} yells a number and does not depend on {}", name, HUMAN));
+        };
+
+        let (left_human, right_human) = (human.contains(left.as_str()), human.contains(right.as_str()));
+        if left_human && right_human {
+            return Err(format!("{} depends on {} on both sides", name, HUMAN));
+        }
+
+        if left_human {
+            let known: T = self.evaluate(right)?;
+            let next = match op {
+                Operator::Add => Operator::Subtract.apply(expected, known),
+                Operator::Subtract => Operator::Add.apply(expected, known),
+                Operator::Multiply => Operator::Divide.apply(expected, known),
+                Operator::Divide => Operator::Multiply.apply(expected, known),
+            };
+            self.solve_with(left, next.map_err(|e| format!("No integer solution at {}: {}", name, e))?, human)
+        } else if right_human {
+            let known: T = self.evaluate(left)?;
+            let next = match op {
+                Operator::Add => Operator::Subtract.apply(expected, known),
+                Operator::Subtract => Operator::Subtract.apply(known, expected),
+                Operator::Multiply => Operator::Divide.apply(expected, known),
+                Operator::Divide => Operator::Divide.apply(known, expected),
+            };
+            self.solve_with(right, next.map_err(|e| format!("No integer solution at {}: {}", name, e))?, human)
+        } else {
+            Err(format!("{} does not depend on {}", name, HUMAN))
+        }
+    }
+
+    /// `root` checks whether both of its monkeys yell the same number
+    pub fn solve_equality<T: MonkeyNumber>(&self) -> Result<T, String> {
+        let Job::Operation(left, _, right) = self.job(ROOT)? else {
+            return Err(format!("{} should compare two monkeys", ROOT));
+        };
+        let human = self.dependents(HUMAN)?;
+        match (human.contains(left.as_str()), human.contains(right.as_str())) {
+            (true, true) => Err(format!("{} depends on {} on both sides", ROOT, HUMAN)),
+            (true, false) => self.solve_with(left, self.evaluate(right)?, &human),
+            (false, true) => self.solve_with(right, self.evaluate(left)?, &human),
+            (false, false) => Err(format!("{} does not depend on {}", ROOT, HUMAN)),
+        }
+    }
+}
+
+fn part1(input: String) -> i128 {
+    let (_, monkeys) = Monkeys::parse(&input).unwrap();
+    monkeys.evaluate(ROOT).unwrap()
+}
+
+fn part2(input: String) -> i128 {
+    let (_, monkeys) = Monkeys::parse(&input).unwrap();
+    monkeys.solve_equality().unwrap()
+}
+
+pub fn process(input: String) {
+    let result = part2(input);
+    println!("The human should yell: {}", result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Job::parse("root: pppw + sjmn").unwrap().1,
+            ("root".to_owned(), Job::Operation("pppw".to_owned(), Operator::Add, "sjmn".to_owned()))
+        );
+        assert_eq!(Job::parse("dbpl: 5").unwrap().1, ("dbpl".to_owned(), Job::Number(5)));
+    }
+
+    #[test]
+    fn test_evaluate_widths() {
+        let input = include_str!("../data/day21_dry.txt");
+        let (_, monkeys) = Monkeys::parse(input).unwrap();
+        assert_eq!(monkeys.evaluate::<i64>(ROOT), Ok(152));
+        assert_eq!(monkeys.solve_equality::<i64>(), Ok(301));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (_, monkeys) = Monkeys::parse("root: aaaa / humn\naaaa: 7\nhumn: 2").unwrap();
+        assert_eq!(monkeys.evaluate::<i64>(ROOT), Err("root: 7 / 2 is not an integer".to_owned()));
+        assert!(monkeys.solve::<i64>(ROOT, 2).unwrap_err().starts_with("No integer solution"));
+        assert_eq!(monkeys.solve::<i64>(ROOT, 7), Ok(1));
+
+        let (_, monkeys) = Monkeys::parse("root: aaaa * humn\naaaa: 9223372036854775807\nhumn: 2").unwrap();
+        assert!(monkeys.evaluate::<i64>(ROOT).unwrap_err().contains("overflows"));
+        assert_eq!(monkeys.evaluate::<i128>(ROOT), Ok(i64::MAX as i128 * 2));
+
+        let (_, monkeys) = Monkeys::parse("root: aaaa + humn\naaaa: humn * bbbb\nbbbb: 3\nhumn: 2").unwrap();
+        assert!(monkeys.solve::<i64>(ROOT, 8).unwrap_err().contains("both sides"));
+
+        let (_, monkeys) = Monkeys::parse("root: aaaa + bbbb\naaaa: humn * cccc\ncccc: 2\nbbbb: humn + dddd\ndddd: 3\nhumn: 5").unwrap();
+        assert_eq!(monkeys.solve_equality::<i64>(), Err("root depends on humn on both sides".to_owned()));
+
+        let (_, monkeys) = Monkeys::parse("root: aaaa + bbbb\naaaa: 4\nbbbb: 5\nhumn: 1").unwrap();
+        assert_eq!(monkeys.solve_equality::<i64>(), Err("root does not depend on humn".to_owned()));
+
+        let (_, monkeys) = Monkeys::parse("root: aaaa / bbbb\naaaa: 7\nbbbb: humn - cccc\ncccc: 2\nhumn: 2").unwrap();
+        assert_eq!(monkeys.evaluate::<i64>(ROOT), Err("root: 7 / 0 divides by zero".to_owned()));
+        assert_eq!(monkeys.solve::<i64>(ROOT, 0), Err("No integer solution at root: 7 / 0 divides by zero".to_owned()));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let (_, monkeys) = Monkeys::parse("root: aaaa + bbbb\naaaa: root + humn\nbbbb: 1\nhumn: 1").unwrap();
+        assert_eq!(monkeys.evaluate::<i64>(ROOT), Err("root depends on itself".to_owned()));
+        // Which monkey of the cycle is named depends on where the search enters it
+        assert!(monkeys.solve_equality::<i64>().unwrap_err().ends_with("depends on itself"));
+        assert!(monkeys.solve::<i64>("aaaa", 3).unwrap_err().ends_with("depends on itself"));
+    }
+
+    #[test]
+    fn test_shared_subexpressions() {
+        // Every monkey in the chain uses the next one twice, so the first yells 2^40 and
+        // walking the chain without remembering what was visited would never finish
+        let name = |i: usize| format!("k{}{}", (b'a' + (i / 26) as u8) as char, (b'a' + (i % 26) as u8) as char);
+        let mut input = format!("root: hhhh + {}\nhhhh: humn + {}\nhumn: 5", name(0), name(0));
+        for i in 0..40 {
+            input += &format!("\n{}: {} + {}", name(i), name(i + 1), name(i + 1));
+        }
+        input += &format!("\n{}: 1", name(40));
+
+        let (_, monkeys) = Monkeys::parse(&input).unwrap();
+        assert_eq!(monkeys.dependents(HUMAN).unwrap(), HashSet::from(["root", "hhhh", "humn"]));
+        assert_eq!(monkeys.evaluate::<i64>(ROOT), Ok(5 + (2i64 << 40)));
+        assert_eq!(monkeys.solve_equality::<i64>(), Ok(0));
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day21_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 152);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day21_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 301);
+    }
+}
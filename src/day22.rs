@@ -0,0 +1,357 @@
+use std::collections::{HashMap, VecDeque};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, value};
+use nom::IResult;
+use nom::multi::many1;
+use crate::grid::{Facing, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Instruction {
+    Forward(u32),
+    TurnLeft,
+    TurnRight,
+}
+
+impl Instruction {
+    // 10R5L5R10L4R5L5
+    pub fn parse_path(input: &str) -> IResult<&str, Vec<Instruction>> {
+        many1(alt((
+            map(nom::character::complete::u32, Instruction::Forward),
+            value(Instruction::TurnLeft, tag("L")),
+            value(Instruction::TurnRight, tag("R")),
+        )))(input)
+    }
+}
+
+struct Board {
+    rows: Vec<Vec<char>>,
+}
+
+impl Board {
+    pub fn parse(input: &str) -> (Board, Vec<Instruction>) {
+        let (map, path) = input
+            .split_once("\n\n")
+            .or_else(|| input.split_once("\r\n\r\n"))
+            .expect("The map and the path should be separated by an empty line");
+
+        let rows = map.lines().map(|l| l.chars().collect()).collect();
+        let (_, path) = Instruction::parse_path(path.trim()).unwrap();
+        (Board { rows }, path)
+    }
+
+    /// `None` outside of the map, including the spaces around it
+    pub fn get(&self, p: Point) -> Option<char> {
+        if p.x < 0 || p.y < 0 {
+            return None;
+        }
+        match self.rows.get(p.y as usize)?.get(p.x as usize)? {
+            ' ' => None,
+            c => Some(*c),
+        }
+    }
+
+    pub fn start(&self) -> Point {
+        let x = self.rows[0].iter().position(|c| *c == '.').unwrap();
+        Point::new(x as i32, 0)
+    }
+
+    pub fn tiles(&self) -> usize {
+        self.rows.iter().flatten().filter(|c| **c != ' ').count()
+    }
+
+    /// Follows the path, `wrap` tells where we end up when walking off the map
+    pub fn walk(&self, path: &[Instruction], wrap: &dyn Fn(Point, Facing) -> (Point, Facing)) -> (Point, Facing) {
+        let mut position = self.start();
+        let mut facing = Facing::Right;
+
+        for instruction in path {
+            match instruction {
+                Instruction::TurnLeft => facing = facing.turn_left(),
+                Instruction::TurnRight => facing = facing.turn_right(),
+                Instruction::Forward(steps) => {
+                    for _ in 0..*steps {
+                        let (next, next_facing) = self.step(position, facing, wrap);
+                        if self.get(next) == Some('#') {
+                            break;
+                        }
+                        position = next;
+                        facing = next_facing;
+                    }
+                }
+            }
+        }
+        (position, facing)
+    }
+
+    fn step(&self, position: Point, facing: Facing, wrap: &dyn Fn(Point, Facing) -> (Point, Facing)) -> (Point, Facing) {
+        let next = position + facing.delta();
+        match self.get(next) {
+            Some(_) => (next, facing),
+            None => wrap(position, facing),
+        }
+    }
+
+    /// Walking off an edge brings us back on the opposite side of the same row or column
+    pub fn flat_wrap(&self, position: Point, facing: Facing) -> (Point, Facing) {
+        let back = facing.opposite().delta();
+        let mut position = position;
+        while self.get(position + back).is_some() {
+            position = position + back;
+        }
+        (position, facing)
+    }
+}
+
+type Vector3 = [i32; 3];
+
+fn add3(a: Vector3, b: Vector3) -> Vector3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn neg3(a: Vector3) -> Vector3 {
+    [-a[0], -a[1], -a[2]]
+}
+
+/// Where a face of the net ends up on the unit cube: the 3D position of its top left corner,
+/// the directions its rows and columns run in and the normal pointing into the cube
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    tile: Point,
+    corner: Vector3,
+    right: Vector3,
+    down: Vector3,
+    inward: Vector3,
+}
+
+impl Face {
+    /// Folds the neighbouring face of the net around the shared edge
+    pub fn fold(&self, facing: Facing) -> Face {
+        let tile = self.tile + facing.delta();
+        match facing {
+            Facing::Right => Face { tile, corner: add3(self.corner, self.right), right: self.inward, inward: neg3(self.right), ..*self },
+            Facing::Left => Face { tile, corner: add3(self.corner, self.inward), right: neg3(self.inward), inward: self.right, ..*self },
+            Facing::Down => Face { tile, corner: add3(self.corner, self.down), down: self.inward, inward: neg3(self.down), ..*self },
+            Facing::Up => Face { tile, corner: add3(self.corner, self.inward), down: neg3(self.inward), inward: self.down, ..*self },
+        }
+    }
+
+    /// The corners of the edge we cross when leaving the face towards `facing`.
+    /// Edges run clockwise, so the two faces sharing an edge see it in opposite directions.
+    pub fn edge(&self, facing: Facing) -> (Vector3, Vector3) {
+        let top_left = self.corner;
+        let top_right = add3(self.corner, self.right);
+        let bottom_left = add3(self.corner, self.down);
+        let bottom_right = add3(top_right, self.down);
+        match facing {
+            Facing::Up => (top_left, top_right),
+            Facing::Right => (top_right, bottom_right),
+            Facing::Down => (bottom_right, bottom_left),
+            Facing::Left => (bottom_left, top_left),
+        }
+    }
+}
+
+/// The board folded into a cube, works for any of the 11 cube nets
+struct Cube {
+    size: i32,
+    faces: Vec<Face>,
+    face_at: HashMap<Point, usize>,
+    /// Leaving face `a` towards `facing` enters face `b` through its edge on side `side`
+    stitches: HashMap<(usize, Facing), (usize, Facing)>,
+}
+
+impl Cube {
+    pub fn fold(board: &Board) -> Cube {
+        let size = ((board.tiles() / 6) as f64).sqrt() as i32;
+        let start = board.start();
+        let first = Face {
+            tile: Point::new(start.x / size, start.y / size),
+            corner: [0, 0, 0],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+            inward: [0, 0, 1],
+        };
+
+        // Walk the net and fold every face we reach onto the cube
+        let mut faces = vec![first];
+        let mut face_at = HashMap::from([(first.tile, 0)]);
+        let mut queue = VecDeque::from([first]);
+        while let Some(face) = queue.pop_front() {
+            for facing in Facing::ALL {
+                let next = face.fold(facing);
+                let on_board = board.get(Point::new(next.tile.x * size, next.tile.y * size)).is_some();
+                if on_board && !face_at.contains_key(&next.tile) {
+                    face_at.insert(next.tile, faces.len());
+                    faces.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        assert_eq!(faces.len(), 6, "The board should fold into a cube");
+
+        let mut stitches = HashMap::new();
+        for (a, face) in faces.iter().enumerate() {
+            for facing in Facing::ALL {
+                let (from, to) = face.edge(facing);
+                let (b, side) = faces
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(b, other)| Facing::ALL.map(|side| (b, side, other.edge(side))))
+                    .find(|(_, _, edge)| *edge == (to, from))
+                    .map(|(b, side, _)| (b, side))
+                    .expect("Every edge of a cube is shared by two faces");
+                stitches.insert((a, facing), (b, side));
+            }
+        }
+
+        Cube { size, faces, face_at, stitches }
+    }
+
+    /// Cell of `face` on the edge towards `side`, counted clockwise along the edge
+    fn edge_cell(&self, face: usize, side: Facing, offset: i32) -> Point {
+        let n = self.size;
+        let local = match side {
+            Facing::Up => Point::new(offset, 0),
+            Facing::Right => Point::new(n - 1, offset),
+            Facing::Down => Point::new(n - 1 - offset, n - 1),
+            Facing::Left => Point::new(0, n - 1 - offset),
+        };
+        let tile = self.faces[face].tile;
+        Point::new(tile.x * n + local.x, tile.y * n + local.y)
+    }
+
+    fn edge_offset(&self, position: Point, side: Facing) -> i32 {
+        let n = self.size;
+        let (x, y) = (position.x.rem_euclid(n), position.y.rem_euclid(n));
+        match side {
+            Facing::Up => x,
+            Facing::Right => y,
+            Facing::Down => n - 1 - x,
+            Facing::Left => n - 1 - y,
+        }
+    }
+
+    /// Walking off a face continues on the face sharing that edge of the cube
+    pub fn wrap(&self, position: Point, facing: Facing) -> (Point, Facing) {
+        let face = self.face_at[&Point::new(position.x.div_euclid(self.size), position.y.div_euclid(self.size))];
+        let (next_face, side) = self.stitches[&(face, facing)];
+        let offset = self.edge_offset(position, facing);
+        let next = self.edge_cell(next_face, side, self.size - 1 - offset);
+        (next, side.opposite())
+    }
+}
+
+fn password(position: Point, facing: Facing) -> i32 {
+    1000 * (position.y + 1) + 4 * (position.x + 1) + facing as i32
+}
+
+fn part1(input: String) -> i32 {
+    let (board, path) = Board::parse(&input);
+    let (position, facing) = board.walk(&path, &|p, f| board.flat_wrap(p, f));
+    password(position, facing)
+}
+
+fn part2(input: String) -> i32 {
+    let (board, path) = Board::parse(&input);
+    let cube = Cube::fold(&board);
+    let (position, facing) = board.walk(&path, &|p, f| cube.wrap(p, f));
+    password(position, facing)
+}
+
+pub fn process(input: String) {
+    let result = part2(input);
+    println!("Final password: {}", result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    const NETS: [&str; 11] = [
+        "x...\nxxxx\nx...",
+        "x...\nxxxx\n.x..",
+        "x...\nxxxx\n..x.",
+        "x...\nxxxx\n...x",
+        ".x..\nxxxx\n.x..",
+        ".x..\nxxxx\n..x.",
+        "xx..\n.xxx\n.x..",
+        "xx..\n.xxx\n..x.",
+        "xx..\n.xxx\n...x",
+        "xx..\n.xx.\n..xx",
+        "xxx..\n..xxx",
+    ];
+
+    /// Blows every `x` of the net up into an empty face of the given size
+    fn empty_board(net: &str, size: usize) -> Board {
+        let rows = net
+            .lines()
+            .flat_map(|line| {
+                let row: Vec<char> = line
+                    .chars()
+                    .flat_map(|c| std::iter::repeat_n(if c == 'x' { '.' } else { ' ' }, size))
+                    .collect();
+                std::iter::repeat_n(row, size)
+            })
+            .collect();
+        Board { rows }
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = include_str!("../data/day22_dry.txt");
+        let (board, path) = Board::parse(input);
+        assert_eq!(board.start(), Point::new(8, 0));
+        assert_eq!(board.get(Point::new(11, 0)), Some('#'));
+        assert_eq!(board.get(Point::new(0, 0)), None);
+        assert_eq!(path[..3], [Instruction::Forward(10), Instruction::TurnRight, Instruction::Forward(5)]);
+    }
+
+    #[test]
+    fn test_all_nets_fold() {
+        let size = 3;
+        for net in NETS {
+            let board = empty_board(net, size);
+            let cube = Cube::fold(&board);
+
+            for (y, row) in board.rows.iter().enumerate() {
+                for x in 0..row.len() {
+                    let start = Point::new(x as i32, y as i32);
+                    if board.get(start).is_none() {
+                        continue;
+                    }
+                    for facing in Facing::ALL {
+                        // Going around the cube in a straight line brings us back where we started
+                        let mut state = (start, facing);
+                        for _ in 0..4 * size {
+                            state = board.step(state.0, state.1, &|p, f| cube.wrap(p, f));
+                            assert!(board.get(state.0).is_some(), "{} left the board at {:?}", net, state);
+                        }
+                        assert_eq!(state, (start, facing), "Walking around the cube of\n{}", net);
+
+                        // Stepping over an edge and turning around leads back
+                        let (next, next_facing) = board.step(start, facing, &|p, f| cube.wrap(p, f));
+                        let back = board.step(next, next_facing.opposite(), &|p, f| cube.wrap(p, f));
+                        assert_eq!(back, (start, facing.opposite()), "Stepping back on the cube of\n{}", net);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day22_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 6032);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day22_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 5031);
+    }
+}
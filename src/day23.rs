@@ -0,0 +1,190 @@
+use std::collections::{HashMap, HashSet};
+use crate::grid::Point;
+
+const NORTH: [Point; 3] = [Point { x: -1, y: -1 }, Point { x: 0, y: -1 }, Point { x: 1, y: -1 }];
+const SOUTH: [Point; 3] = [Point { x: -1, y: 1 }, Point { x: 0, y: 1 }, Point { x: 1, y: 1 }];
+const WEST: [Point; 3] = [Point { x: -1, y: -1 }, Point { x: -1, y: 0 }, Point { x: -1, y: 1 }];
+const EAST: [Point; 3] = [Point { x: 1, y: -1 }, Point { x: 1, y: 0 }, Point { x: 1, y: 1 }];
+
+/// Checked in this order in the first round, the first direction moves to the back after every round.
+/// The middle of the three cells is where the elf goes.
+const PROPOSALS: [[Point; 3]; 4] = [NORTH, SOUTH, WEST, EAST];
+
+const NEIGHBOURS: [Point; 8] = [
+    Point { x: -1, y: -1 }, Point { x: 0, y: -1 }, Point { x: 1, y: -1 },
+    Point { x: -1, y: 0 }, Point { x: 1, y: 0 },
+    Point { x: -1, y: 1 }, Point { x: 0, y: 1 }, Point { x: 1, y: 1 },
+];
+
+/// Only the positions of the elves are stored, so the grove can spread as far as it likes
+struct Grove {
+    elves: HashSet<Point>,
+    rounds: usize,
+}
+
+impl Grove {
+    pub fn parse(input: &str) -> Grove {
+        let elves = input
+            .lines()
+            .enumerate()
+            .flat_map(|(y, l)| l
+                .chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(x, _)| Point::new(x as i32, y as i32)))
+            .collect();
+
+        Grove { elves, rounds: 0 }
+    }
+
+    fn bounds(&self) -> (Point, Point) {
+        let min = Point::new(
+            self.elves.iter().map(|e| e.x).min().unwrap_or(0),
+            self.elves.iter().map(|e| e.y).min().unwrap_or(0),
+        );
+        let max = Point::new(
+            self.elves.iter().map(|e| e.x).max().unwrap_or(0),
+            self.elves.iter().map(|e| e.y).max().unwrap_or(0),
+        );
+        (min, max)
+    }
+
+    fn is_occupied(&self, p: Point) -> bool {
+        self.elves.contains(&p)
+    }
+
+    fn proposal(&self, elf: Point) -> Option<Point> {
+        if NEIGHBOURS.iter().all(|n| !self.is_occupied(elf + *n)) {
+            return None;
+        }
+        (0..4)
+            .map(|i| &PROPOSALS[(self.rounds + i) % 4])
+            .find(|cells| cells.iter().all(|c| !self.is_occupied(elf + *c)))
+            .map(|cells| elf + cells[1])
+    }
+
+    /// Plays one round and tells whether any elf moved
+    pub fn round(&mut self) -> bool {
+        // Every proposed cell with the elf proposing it, or `None` once a second elf proposes it too
+        let mut proposals: HashMap<Point, Option<Point>> = HashMap::new();
+        for elf in &self.elves {
+            if let Some(target) = self.proposal(*elf) {
+                proposals
+                    .entry(target)
+                    .and_modify(|from| *from = None)
+                    .or_insert(Some(*elf));
+            }
+        }
+
+        let mut moved = false;
+        for (target, from) in proposals {
+            if let Some(from) = from {
+                self.elves.remove(&from);
+                self.elves.insert(target);
+                moved = true;
+            }
+        }
+
+        self.rounds += 1;
+        moved
+    }
+
+    /// Empty ground tiles in the smallest rectangle containing all the elves
+    pub fn empty_ground(&self) -> usize {
+        let (min, max) = self.bounds();
+        ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize - self.elves.len()
+    }
+
+    /// The smallest rectangle containing all the elves, `#` for an elf and `.` for ground
+    pub fn render(&self) -> String {
+        let (min, max) = self.bounds();
+        (min.y..=max.y)
+            .map(|y| (min.x..=max.x)
+                .map(|x| if self.is_occupied(Point::new(x, y)) { '#' } else { '.' })
+                .collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn part1(input: String) -> usize {
+    let mut grove = Grove::parse(&input);
+    for _ in 0..10 {
+        grove.round();
+    }
+    grove.empty_ground()
+}
+
+fn part2(input: String) -> usize {
+    let mut grove = Grove::parse(&input);
+    while grove.round() {}
+    grove.rounds
+}
+
+pub fn process(input: String) {
+    let result = part2(input);
+    println!("First round where no elf moves: {}", result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_small_example() {
+        let mut grove = Grove::parse(".....\n..##.\n..#..\n.....\n..##.\n.....");
+        grove.round();
+        assert_eq!(grove.render(), "##\n..\n#.\n.#\n#.");
+        grove.round();
+        grove.round();
+        assert_eq!(grove.render(), "..#..\n....#\n#....\n....#\n.....\n..#..");
+        assert!(!grove.round());
+        assert_eq!(grove.rounds, 4);
+    }
+
+    #[test]
+    fn test_render_after_rounds() {
+        let input = include_str!("../data/day23_dry.txt");
+        let mut grove = Grove::parse(input);
+        assert_eq!(grove.render(), input);
+        grove.round();
+        assert_eq!(grove.render(), "\
+.....#...
+...#...#.
+.#..#.#..
+.....#..#
+..#.#.##.
+#..#.#...
+#.#.#.##.
+.........
+..#..#...");
+    }
+
+    #[test]
+    fn test_spreads_out() {
+        // A solid block keeps spreading until no elf has a neighbour left
+        let input = vec!["#".repeat(20); 20].join("\n");
+        let mut grove = Grove::parse(&input);
+        while grove.round() {}
+        assert_eq!(grove.elves.len(), 400);
+        let (min, max) = grove.bounds();
+        assert!(min.x < 0 && min.y < 0 && max.x > 19 && max.y > 19);
+        assert!(grove.elves.iter().all(|e| NEIGHBOURS.iter().all(|n| !grove.is_occupied(*e + *n))));
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day23_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 110);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day23_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 20);
+    }
+}
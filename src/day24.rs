@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+use crate::grid::{Facing, Point};
+use crate::checked::gcd;
+
+/// The valley without its walls, `(0, 0)` is the top left cell inside.
+/// The entrance sits just above the first column and the exit just below the last one.
+struct Valley {
+    width: i32,
+    height: i32,
+    /// The blizzards come back to their starting places after this many minutes
+    period: usize,
+    /// Which cells are covered by a blizzard at every minute of the period
+    occupied: Vec<Vec<bool>>,
+}
+
+impl Valley {
+    pub fn parse(input: &str) -> Valley {
+        let lines: Vec<&str> = input.lines().collect();
+        let width = lines[0].len() as i32 - 2;
+        let height = lines.len() as i32 - 2;
+
+        let blizzards: Vec<(Point, Facing)> = lines[1..lines.len() - 1]
+            .iter()
+            .enumerate()
+            .flat_map(|(y, l)| l
+                .chars()
+                .skip(1)
+                .enumerate()
+                .filter_map(move |(x, c)| {
+                    let facing = match c {
+                        '>' => Facing::Right,
+                        'v' => Facing::Down,
+                        '<' => Facing::Left,
+                        '^' => Facing::Up,
+                        _ => return None,
+                    };
+                    Some((Point::new(x as i32, y as i32), facing))
+                }))
+            .collect();
+
+        let period = width as usize * height as usize / gcd(width as usize, height as usize);
+        let occupied = (0..period)
+            .map(|t| {
+                let mut cells = vec![false; (width * height) as usize];
+                for (start, facing) in &blizzards {
+                    let delta = facing.delta();
+                    let x = (start.x + delta.x * t as i32).rem_euclid(width);
+                    let y = (start.y + delta.y * t as i32).rem_euclid(height);
+                    cells[(y * width + x) as usize] = true;
+                }
+                cells
+            })
+            .collect();
+
+        Valley { width, height, period, occupied }
+    }
+
+    pub fn entrance(&self) -> Point {
+        Point::new(0, -1)
+    }
+
+    pub fn exit(&self) -> Point {
+        Point::new(self.width - 1, self.height)
+    }
+
+    /// Index of the cell among the inside cells followed by the entrance and the exit,
+    /// `None` for walls and anything outside the valley
+    fn cell(&self, p: Point) -> Option<usize> {
+        if p == self.entrance() {
+            Some((self.width * self.height) as usize)
+        } else if p == self.exit() {
+            Some((self.width * self.height) as usize + 1)
+        } else if p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height {
+            Some((p.y * self.width + p.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn is_free(&self, p: Point, minute: usize) -> bool {
+        match self.cell(p) {
+            Some(cell) if cell < (self.width * self.height) as usize => !self.occupied[minute % self.period][cell],
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// The minute of the earliest arrival at `to` when leaving `from` at minute `start`.
+    /// Since the blizzards repeat, a state is a cell together with the minute within the period.
+    pub fn crossing(&self, from: Point, to: Point, start: usize) -> Option<usize> {
+        let cells = (self.width * self.height) as usize + 2;
+        let mut visited = vec![false; cells * self.period];
+        let mut queue = VecDeque::new();
+
+        visited[(start % self.period) * cells + self.cell(from)?] = true;
+        queue.push_back((from, start));
+
+        while let Some((position, minute)) = queue.pop_front() {
+            if position == to {
+                return Some(minute);
+            }
+
+            let next_minute = minute + 1;
+            let moves = Facing::ALL.iter().map(|f| position + f.delta()).chain([position]);
+            for next in moves {
+                if !self.is_free(next, next_minute) {
+                    continue;
+                }
+                let state = (next_minute % self.period) * cells + self.cell(next).unwrap();
+                if !visited[state] {
+                    visited[state] = true;
+                    queue.push_back((next, next_minute));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn part1(input: String) -> usize {
+    let valley = Valley::parse(&input);
+    valley.crossing(valley.entrance(), valley.exit(), 0).expect("There should be a way through")
+}
+
+/// There to the exit, back to the entrance for the snacks and to the exit again
+fn part2(input: String) -> usize {
+    let valley = Valley::parse(&input);
+    let there = valley.crossing(valley.entrance(), valley.exit(), 0).expect("There should be a way through");
+    let back = valley.crossing(valley.exit(), valley.entrance(), there).expect("There should be a way back");
+    valley.crossing(valley.entrance(), valley.exit(), back).expect("There should be a way through again")
+}
+
+pub fn process(input: String) {
+    let result = part2(input);
+    println!("Minutes to reach the goal, go back and reach it again: {}", result);
+}
+
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_blizzards_move() {
+        let valley = Valley::parse("#.#####\n#.....#\n#>....#\n#.....#\n#...v.#\n#.....#\n#####.#");
+        assert_eq!(valley.period, 5);
+        assert!(!valley.is_free(Point::new(0, 1), 0));
+        assert!(!valley.is_free(Point::new(1, 1), 1));
+        assert!(!valley.is_free(Point::new(3, 4), 1));
+        // Both wrap around and meet in the same cell
+        assert!(!valley.is_free(Point::new(3, 1), 3));
+        assert!(!valley.is_free(Point::new(0, 1), 5));
+        assert!(valley.is_free(valley.entrance(), 0));
+        assert!(!valley.is_free(Point::new(1, -1), 0));
+    }
+
+    #[test]
+    fn test_example1() {
+        let input = include_str!("../data/day24_dry.txt");
+        let result = part1(input.to_owned());
+        assert_eq!(result, 18);
+    }
+
+    #[test]
+    fn test_example2() {
+        let input = include_str!("../data/day24_dry.txt");
+        let result = part2(input.to_owned());
+        assert_eq!(result, 54);
+    }
+}
@@ -1,93 +1,337 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
 
-#[derive(Debug)]
-struct SnafuNumber {
-    /// Index 0 is the least significant bit
-    parts: Vec<i8>
+/// An odd base with its digits ordered from the lowest value, e.g. `=-012` for -2..=2
+trait BalancedBase: Debug + Clone + PartialEq + Eq {
+    const BASE: i64;
+    const DIGITS: &'static [char];
 }
 
-impl SnafuNumber {
-    fn parse_digit(i: char) -> i8 {
-        match i {
-            '-' => -1,
-            '=' => -2,
-            i => i.to_string().parse().unwrap()
-        }
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snafu;
+
+impl BalancedBase for Snafu {
+    const BASE: i64 = 5;
+    const DIGITS: &'static [char] = &['=', '-', '0', '1', '2'];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ternary;
+
+impl BalancedBase for Ternary {
+    const BASE: i64 = 3;
+    const DIGITS: &'static [char] = &['-', '0', '+'];
+}
+
+/// A balanced number of any size in base `B::BASE`, digits are -BASE/2..=BASE/2
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BalancedNumber<B: BalancedBase> {
+    /// Index 0 is the least significant digit, there are no leading (trailing in the vector) zeros
+    parts: Vec<i8>,
+    base: PhantomData<B>
+}
+
+type SnafuNumber = BalancedNumber<Snafu>;
+type TernaryNumber = BalancedNumber<Ternary>;
+
+impl<B: BalancedBase> BalancedNumber<B> {
+    const HALF: i64 = {
+        assert!(B::BASE % 2 == 1 && B::BASE as usize == B::DIGITS.len(), "A balanced base needs an odd number of digits");
+        assert!(B::BASE >= 3, "A balanced base needs a digit on each side of zero");
+        assert!(B::BASE <= 255, "Digits of a balanced base above 255 do not fit an i8");
+        B::BASE / 2
+    };
+    
+    fn parse_digit(i: char) -> Option<i8> {
+        B::DIGITS.iter().position(|d| *d == i).map(|p| (p as i64 - Self::HALF) as i8)
     }
     
     fn format_digit(i: &i8) -> char {
-        match i {
-            -1 => '-',
-            -2 => '=',
-            other => other.to_string().chars().next().unwrap()
+        B::DIGITS[(*i as i64 + Self::HALF) as usize]
+    }
+    
+    /// Only accepts the form produced by `to_string`, so zero is a single digit and
+    /// nothing else starts with one
+    pub fn parse_canonical(input: &str) -> Result<Self, ParseBalancedError> {
+        let number = Self::try_from(input)?;
+        if input.chars().count() > 1 && input.starts_with(Self::format_digit(&0)) {
+            return Err(ParseBalancedError::LeadingZero);
         }
+        Ok(number)
     }
     
-    fn parse(input: &str) -> Self {
-        let parts : Vec<i8> = input.chars().map(SnafuNumber::parse_digit).rev().collect();
-        Self { parts }
+    pub fn zero() -> Self {
+        BalancedNumber { parts: vec![], base: PhantomData }
     }
-}
-
-impl From<&SnafuNumber> for u128 {
-    fn from(value: &SnafuNumber) -> Self {
-        let mut v = 0;
+    
+    /// Carries every digit into -HALF..=HALF, the digits may be any size before that
+    fn normalize(raw: Vec<i64>) -> Self {
+        let mut parts = Vec::with_capacity(raw.len() + 1);
+        let mut carry = 0i64;
+        let mut i = 0;
         
-        for i in 0..value.parts.len() {
-            v += value.parts[i] as i128 * (5 as i128).pow(i as u32)
+        while i < raw.len() || carry != 0 {
+            let value = raw.get(i).copied().unwrap_or(0) + carry;
+            carry = (value + Self::HALF).div_euclid(B::BASE);
+            parts.push((value - carry * B::BASE) as i8);
+            i += 1;
         }
         
-        v as u128
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        BalancedNumber { parts, base: PhantomData }
     }
+    
+    pub fn signum(&self) -> i8 {
+        // The most significant digit outweighs all the others together
+        self.parts.last().map(|d| d.signum()).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseBalancedError {
+    Empty,
+    /// `position` counts characters from the left, starting at 0
+    InvalidDigit { digit: char, position: usize },
+    LeadingZero,
 }
 
-impl From<u128> for SnafuNumber {
-    fn from(value: u128) -> Self {
-        let mut parts = vec![]; // for carryover
+impl fmt::Display for ParseBalancedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBalancedError::Empty => write!(f, "Empty number"),
+            ParseBalancedError::InvalidDigit { digit, position } => 
+                write!(f, "Invalid digit '{}' at position {}", digit, position),
+            ParseBalancedError::LeadingZero => write!(f, "Number has a leading zero"),
+        }
+    }
+}
+
+impl std::error::Error for ParseBalancedError {}
+
+/// A number in the input file which could not be read
+#[derive(Debug, PartialEq, Eq)]
+struct InputError {
+    /// Starts at 1 like in an editor
+    line: usize,
+    error: ParseBalancedError,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl<B: BalancedBase> TryFrom<&str> for BalancedNumber<B> {
+    type Error = ParseBalancedError;
+
+    /// Accepts leading zeros, use `parse_canonical` to reject them
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        if input.is_empty() {
+            return Err(ParseBalancedError::Empty);
+        }
+        let raw = input
+            .chars()
+            .enumerate()
+            .map(|(position, digit)| {
+                Self::parse_digit(digit)
+                    .map(i64::from)
+                    .ok_or(ParseBalancedError::InvalidDigit { digit, position })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::normalize(raw.into_iter().rev().collect()))
+    }
+}
+
+impl<B: BalancedBase> FromStr for BalancedNumber<B> {
+    type Err = ParseBalancedError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::try_from(input)
+    }
+}
+
+impl<B: BalancedBase> TryFrom<&BalancedNumber<B>> for u128 {
+    type Error = String;
+
+    fn try_from(value: &BalancedNumber<B>) -> Result<Self, Self::Error> {
+        if value.signum() < 0 {
+            return Err(format!("{value} is negative"));
+        }
+        let base = B::BASE as u128;
+        value.parts.iter().rev().try_fold(0u128, |v, d| {
+            // Every prefix of a positive number is positive, so borrowing from it keeps
+            // `v * base` from overflowing when a negative digit brings the total back in range
+            let next = if *d >= 0 {
+                v.checked_mul(base).and_then(|v| v.checked_add(*d as u128))
+            } else {
+                (v - 1).checked_mul(base).and_then(|v| v.checked_add((B::BASE + *d as i64) as u128))
+            };
+            next.ok_or(format!("{value} does not fit into u128"))
+        })
+    }
+}
+
+impl<B: BalancedBase> TryFrom<&BalancedNumber<B>> for i128 {
+    type Error = String;
+
+    fn try_from(value: &BalancedNumber<B>) -> Result<Self, Self::Error> {
+        let error = || format!("{value} does not fit into i128");
+        if value.signum() < 0 {
+            let magnitude = u128::try_from(&-value).map_err(|_| error())?;
+            // i128::MIN has no positive counterpart
+            if magnitude > i128::MIN.unsigned_abs() {
+                return Err(error());
+            }
+            Ok((magnitude as i128).wrapping_neg())
+        } else {
+            let magnitude = u128::try_from(value).map_err(|_| error())?;
+            i128::try_from(magnitude).map_err(|_| error())
+        }
+    }
+}
+
+impl<B: BalancedBase> From<i128> for BalancedNumber<B> {
+    fn from(value: i128) -> Self {
+        let base = B::BASE as i128;
+        let mut raw = vec![];
         let mut value = value;
         
-        while value > 0 {
-            let remainder = value % 5;
-            value = value / 5;
-            parts.push(remainder as i8);
+        while value != 0 {
+            // Carries before dividing so nothing overflows near i128::MIN or i128::MAX
+            let mut digit = value.rem_euclid(base);
+            value = value.div_euclid(base);
+            if digit > base / 2 {
+                digit -= base;
+                value += 1;
+            }
+            raw.push(digit as i64);
         }
         
-        // The correct order is where index 0 is value of 5^0
-        //parts.reverse();
-        
-        let mut carryover = 0;
-        for i in 0..parts.len() {
-            parts[i] += carryover;
-            carryover = 0;
-            
-            if parts[i] > 2 {
-                parts[i] = parts[i] - 5;
-                carryover = 1;
+        Self::normalize(raw)
+    }
+}
+
+impl<B: BalancedBase> fmt::Display for BalancedNumber<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.parts.is_empty() {
+            return write!(f, "{}", Self::format_digit(&0));
+        }
+        let digits: String = self.parts.iter().rev().map(Self::format_digit).collect();
+        write!(f, "{digits}")
+    }
+}
+
+impl<B: BalancedBase> Neg for &BalancedNumber<B> {
+    type Output = BalancedNumber<B>;
+
+    fn neg(self) -> Self::Output {
+        BalancedNumber { parts: self.parts.iter().map(|d| -d).collect(), base: PhantomData }
+    }
+}
+
+impl<B: BalancedBase> Add for &BalancedNumber<B> {
+    type Output = BalancedNumber<B>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let raw = (0..self.parts.len().max(rhs.parts.len()))
+            .map(|i| {
+                *self.parts.get(i).unwrap_or(&0) as i64 + *rhs.parts.get(i).unwrap_or(&0) as i64
+            })
+            .collect();
+        BalancedNumber::normalize(raw)
+    }
+}
+
+impl<B: BalancedBase> Sub for &BalancedNumber<B> {
+    type Output = BalancedNumber<B>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs
+    }
+}
+
+impl<B: BalancedBase> Mul for &BalancedNumber<B> {
+    type Output = BalancedNumber<B>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.parts.is_empty() || rhs.parts.is_empty() {
+            return BalancedNumber::zero();
+        }
+        let mut raw = vec![0i64; self.parts.len() + rhs.parts.len()];
+        for (i, a) in self.parts.iter().enumerate() {
+            for (j, b) in rhs.parts.iter().enumerate() {
+                raw[i + j] += *a as i64 * *b as i64;
             }
         }
-        if carryover > 0 {
-            parts.push(carryover);
+        BalancedNumber::normalize(raw)
+    }
+}
+
+macro_rules! forward_owned_op {
+    ($op:ident, $method:ident) => {
+        impl<B: BalancedBase> $op for BalancedNumber<B> {
+            type Output = BalancedNumber<B>;
+
+            fn $method(self, rhs: Self) -> Self::Output {
+                (&self).$method(&rhs)
+            }
         }
-        
-        SnafuNumber { parts }
+    };
+}
+
+forward_owned_op!(Add, add);
+forward_owned_op!(Sub, sub);
+forward_owned_op!(Mul, mul);
+
+impl<B: BalancedBase> Neg for BalancedNumber<B> {
+    type Output = BalancedNumber<B>;
+
+    fn neg(self) -> Self::Output {
+        -&self
     }
 }
 
-impl ToString for SnafuNumber {
-    fn to_string(&self) -> String {
-        self.parts.iter().rev().map(SnafuNumber::format_digit).collect()
+impl<'a, B: BalancedBase> Sum<&'a BalancedNumber<B>> for BalancedNumber<B> {
+    fn sum<I: Iterator<Item = &'a BalancedNumber<B>>>(iter: I) -> Self {
+        iter.fold(BalancedNumber::zero(), |total, n| &total + n)
     }
 }
 
-fn parse_input(input: &str) -> Vec<SnafuNumber> {
-    input.lines().map(SnafuNumber::parse).collect()
+impl<B: BalancedBase> Ord for BalancedNumber<B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self - other).signum().cmp(&0)
+    }
 }
 
-fn part1(input: String) -> String {
-    let numbers = parse_input(&input);
+impl<B: BalancedBase> PartialOrd for BalancedNumber<B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<SnafuNumber>, InputError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| SnafuNumber::try_from(l).map_err(|error| InputError { line: i + 1, error }))
+        .collect()
+}
+
+fn part1(input: String) -> Result<String, InputError> {
+    let numbers = parse_input(&input)?;
     
-    let dec_total: u128 = numbers.iter().map(u128::from).sum();
-    let snafu_total = SnafuNumber::from(dec_total);
-    snafu_total.to_string()
+    let snafu_total: SnafuNumber = numbers.iter().sum();
+    Ok(snafu_total.to_string())
 }
 
 fn part2(_input: String) -> String {
@@ -95,8 +339,10 @@ fn part2(_input: String) -> String {
 }
 
 pub fn process(input: String) {
-    let result = part1(input);
-    println!("Total in SNAFU: {}", result);
+    match part1(input) {
+        Ok(result) => println!("Total in SNAFU: {}", result),
+        Err(e) => println!("Cannot read the fuel requirements: {}", e),
+    }
 }
 
 
@@ -105,9 +351,15 @@ mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
 
+    impl<B: BalancedBase> BalancedNumber<B> {
+        fn parse(input: &str) -> Self {
+            input.parse().unwrap()
+        }
+    }
+
     #[test]
     fn test_u32_to_snafu() {
-        println!("{}", SnafuNumber::from(1747).to_string());
+        println!("{}", SnafuNumber::from(1747));
         assert_eq!(SnafuNumber::from(1747).to_string(), "1=-0-2");
         assert_eq!(SnafuNumber::from(906).to_string(), "12111");
         assert_eq!(SnafuNumber::from(198).to_string(), "2=0=");
@@ -125,32 +377,188 @@ mod tests {
     
     #[test]
     fn test_snafu_to_u32() {
-        assert_eq!(u128::from(&SnafuNumber::parse("1=-0-2")), 1747);
-        assert_eq!(u128::from(&SnafuNumber::parse("12111")), 906);
-        assert_eq!(u128::from(&SnafuNumber::parse("2=0=")), 198);
-        assert_eq!(u128::from(&SnafuNumber::parse("21")), 11);
-        assert_eq!(u128::from(&SnafuNumber::parse("2=01")), 201);
-        assert_eq!(u128::from(&SnafuNumber::parse("111")), 31);
-        assert_eq!(u128::from(&SnafuNumber::parse("20012")), 1257);
-        assert_eq!(u128::from(&SnafuNumber::parse("112")), 32);
-        assert_eq!(u128::from(&SnafuNumber::parse("1=-1=")), 353);
-        assert_eq!(u128::from(&SnafuNumber::parse("1-12")), 107);
-        assert_eq!(u128::from(&SnafuNumber::parse("12")), 7);
-        assert_eq!(u128::from(&SnafuNumber::parse("1=")), 3);
-        assert_eq!(u128::from(&SnafuNumber::parse("122")), 37);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("1=-0-2")).unwrap(), 1747);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("12111")).unwrap(), 906);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("2=0=")).unwrap(), 198);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("21")).unwrap(), 11);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("2=01")).unwrap(), 201);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("111")).unwrap(), 31);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("20012")).unwrap(), 1257);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("112")).unwrap(), 32);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("1=-1=")).unwrap(), 353);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("1-12")).unwrap(), 107);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("12")).unwrap(), 7);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("1=")).unwrap(), 3);
+        assert_eq!(u128::try_from(&SnafuNumber::parse("122")).unwrap(), 37);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let values = [-3125i128, -1747, -13, -3, -1, 0, 1, 2, 3, 7, 37, 906, 1747, 98765];
+        for a in values {
+            for b in values {
+                let (x, y) = (SnafuNumber::from(a), SnafuNumber::from(b));
+                assert_eq!(i128::try_from(&(&x + &y)).unwrap(), a + b);
+                assert_eq!(i128::try_from(&(&x - &y)).unwrap(), a - b);
+                assert_eq!(i128::try_from(&(&x * &y)).unwrap(), a * b);
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+            }
+            assert_eq!(i128::try_from(&-SnafuNumber::from(a)).unwrap(), -a);
+        }
+        assert_eq!(SnafuNumber::from(-3).to_string(), "-2");
+        assert_eq!(SnafuNumber::from(0).to_string(), "0");
+        assert!(u128::try_from(&SnafuNumber::from(-3)).is_err());
+    }
+
+    #[test]
+    fn test_beyond_u128() {
+        // 5^100
+        let big = SnafuNumber::parse(&format!("1{}", "0".repeat(100)));
+        assert!(u128::try_from(&big).is_err());
+        assert_eq!((&(&big + &big) + &big).to_string(), format!("1={}", "0".repeat(100)));
+        assert_eq!((&big * &big).to_string(), format!("1{}", "0".repeat(200)));
+        assert_eq!(&big - &big, SnafuNumber::zero());
+        assert!(-&big < SnafuNumber::from(i128::MIN));
+        assert!(big > SnafuNumber::from(i128::MAX));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("1=-0-2".parse::<SnafuNumber>().unwrap(), SnafuNumber::from(1747));
+        assert_eq!("-2".parse::<SnafuNumber>().unwrap(), SnafuNumber::from(-3));
+        assert!("12x".parse::<SnafuNumber>().is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            SnafuNumber::try_from("1=7-"),
+            Err(ParseBalancedError::InvalidDigit { digit: '7', position: 2 })
+        );
+        assert_eq!(
+            SnafuNumber::try_from("x"),
+            Err(ParseBalancedError::InvalidDigit { digit: 'x', position: 0 })
+        );
+        assert_eq!(SnafuNumber::try_from(""), Err(ParseBalancedError::Empty));
+        assert_eq!(
+            SnafuNumber::try_from("2 1").unwrap_err().to_string(),
+            "Invalid digit ' ' at position 1"
+        );
+        assert_eq!(
+            TernaryNumber::try_from("+-2"),
+            Err(ParseBalancedError::InvalidDigit { digit: '2', position: 2 })
+        );
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        assert_eq!(SnafuNumber::try_from("001=").unwrap(), SnafuNumber::from(3));
+        assert_eq!(SnafuNumber::parse_canonical("001="), Err(ParseBalancedError::LeadingZero));
+        assert_eq!(SnafuNumber::parse_canonical("1="), Ok(SnafuNumber::from(3)));
+        assert_eq!(SnafuNumber::parse_canonical("0"), Ok(SnafuNumber::zero()));
+        assert_eq!(SnafuNumber::parse_canonical("00"), Err(ParseBalancedError::LeadingZero));
+        assert_eq!(TernaryNumber::parse_canonical("0+"), Err(ParseBalancedError::LeadingZero));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Septenary;
+
+    impl BalancedBase for Septenary {
+        const BASE: i64 = 7;
+        const DIGITS: &'static [char] = &['c', 'b', 'a', '0', '1', '2', '3'];
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Base27;
+
+    impl BalancedBase for Base27 {
+        const BASE: i64 = 27;
+        const DIGITS: &'static [char] = &[
+            'M', 'L', 'K', 'J', 'I', 'H', 'G', 'F', 'E', 'D', 'C', 'B', 'A',
+            '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd',
+        ];
+    }
+
+    /// Deterministic pseudo random values spread over the whole i64 range and around zero
+    fn samples() -> Vec<i128> {
+        let mut state = 0x2545F4914F6CDD1Du64;
+        let mut values: Vec<i128> = (-50..=50).collect();
+        for _ in 0..500 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            values.push(state as i64 as i128);
+            values.push((state >> (state % 60)) as i64 as i128);
+        }
+        values.extend([i128::MIN, i128::MAX, i64::MIN as i128, i64::MAX as i128]);
+        values
+    }
+
+    fn check_round_trip<B: BalancedBase>() {
+        for value in samples() {
+            let number = BalancedNumber::<B>::from(value);
+            let text = number.to_string();
+            assert!(text.chars().all(|c| B::DIGITS.contains(&c)), "{}", text);
+
+            let parsed: BalancedNumber<B> = text.parse().unwrap();
+            assert_eq!(parsed, number, "{} in base {}", value, B::BASE);
+            assert_eq!(i128::try_from(&parsed).unwrap(), value, "{} in base {}", text, B::BASE);
+        }
+    }
+
+    fn check_arithmetic<B: BalancedBase>() {
+        let values: Vec<i128> = samples().into_iter().map(|v| v >> 64).collect();
+        for pair in values.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let (x, y) = (BalancedNumber::<B>::from(a), BalancedNumber::<B>::from(b));
+            assert_eq!(&x + &y, BalancedNumber::from(a + b));
+            assert_eq!(&x - &y, BalancedNumber::from(a - b));
+            assert_eq!(&x * &y, BalancedNumber::from(a * b));
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_across_bases() {
+        check_round_trip::<Ternary>();
+        check_round_trip::<Snafu>();
+        check_round_trip::<Septenary>();
+        check_round_trip::<Base27>();
+
+        check_arithmetic::<Ternary>();
+        check_arithmetic::<Snafu>();
+        check_arithmetic::<Septenary>();
+        check_arithmetic::<Base27>();
+    }
+
+    #[test]
+    fn test_balanced_ternary() {
+        assert_eq!(TernaryNumber::from(0).to_string(), "0");
+        assert_eq!(TernaryNumber::from(2).to_string(), "+-");
+        assert_eq!(TernaryNumber::from(-5).to_string(), "-++");
+        assert_eq!(TernaryNumber::from(8).to_string(), "+0-");
+        assert_eq!(i128::try_from(&TernaryNumber::parse("+-0+")).unwrap(), 19);
     }
 
     #[test]
     fn test_example1() {
         let input = include_str!("../data/day25_dry.txt");
         let result = part1(input.to_owned());
-        assert_eq!(result, "2=-1=0");
+        assert_eq!(result, Ok("2=-1=0".to_owned()));
+    }
+
+    #[test]
+    fn test_input_error() {
+        let result = part1("1=\n2=0=\n1=3\n".to_owned());
+        let error = InputError { line: 3, error: ParseBalancedError::InvalidDigit { digit: '3', position: 2 } };
+        assert_eq!(error.to_string(), "Line 3: Invalid digit '3' at position 2");
+        assert_eq!(result, Err(error));
     }
 
     #[test]
     fn test_example2() {
         let input = include_str!("../data/day25_sharp.txt");
         let result = part1(input.to_owned());
-        assert_eq!(result, "20=022=21--=2--12=-2");
+        assert_eq!(result, Ok("20=022=21--=2--12=-2".to_owned()));
     }
 }
\ No newline at end of file
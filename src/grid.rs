@@ -0,0 +1,56 @@
+use std::ops::Add;
+
+/// A position on a grid, `y` grows downwards like the rows of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Point {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+/// Ordered clockwise starting from the right, the discriminant is the facing's password value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Facing {
+    Right = 0,
+    Down = 1,
+    Left = 2,
+    Up = 3,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::Right, Facing::Down, Facing::Left, Facing::Up];
+
+    pub fn delta(&self) -> Point {
+        match self {
+            Facing::Right => Point::new(1, 0),
+            Facing::Down => Point::new(0, 1),
+            Facing::Left => Point::new(-1, 0),
+            Facing::Up => Point::new(0, -1),
+        }
+    }
+
+    pub fn turn_right(&self) -> Facing {
+        Facing::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(&self) -> Facing {
+        Facing::ALL[(*self as usize + 3) % 4]
+    }
+
+    pub fn opposite(&self) -> Facing {
+        Facing::ALL[(*self as usize + 2) % 4]
+    }
+}
@@ -0,0 +1,34 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+pub(crate) type Color = [u8; 3];
+
+pub(crate) struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Every pixel becomes a `scale` by `scale` square
+    pub fn scaled(&self, scale: usize) -> Image {
+        let pixels = (0..self.height * scale)
+            .flat_map(|y| (0..self.width * scale).map(move |x| (x / scale, y / scale)))
+            .map(|(x, y)| self.pixels[y * self.width + x])
+            .collect();
+        Image { width: self.width * scale, height: self.height * scale, pixels }
+    }
+
+    /// Binary PPM, which any image viewer opens and needs no encoder
+    pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels.concat())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write_ppm(&mut file)?;
+        file.flush()
+    }
+}
@@ -23,7 +23,6 @@ mod day9_2;
 mod day10;
 mod day10_2;
 mod day11;
-mod day11_2;
 mod day12;
 mod day13;
 mod day14;
@@ -36,6 +35,14 @@ mod day17;
 mod day18;
 mod day25;
 mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod grid;
+mod image;
+mod checked;
 
 fn main() {
     let args: Vec<String> = env::args().collect();